@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fmt;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use toml::Value;
+
+use super::ConfigError;
+use crate::game::{Command, InputContext};
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Keys each command is bound to in `InputContext::Game` when the config doesn't change them
+const DEFAULT_GAME_BINDINGS: [(Command, &[&str]); 20] = [
+    (Command::Up, &["k", "up"]),
+    (Command::Down, &["j", "down"]),
+    (Command::Left, &["h", "left"]),
+    (Command::Right, &["l", "right"]),
+    (Command::RunUp, &["K", "shift+up"]),
+    (Command::RunDown, &["J", "shift+down"]),
+    (Command::RunLeft, &["H", "shift+left"]),
+    (Command::RunRight, &["L", "shift+right"]),
+    (Command::NextTheme, &["t"]),
+    (Command::Screenshot, &["p"]),
+    (Command::Repeat, &["."]),
+    (Command::Explore, &["o"]),
+    (Command::TravelTo, &["_"]),
+    (Command::Target, &["tab"]),
+    (Command::Fire, &["f"]),
+    (Command::RecordMacro, &["m"]),
+    (Command::ToggleStats, &["f3"]),
+    (Command::Name, &["n"]),
+    (Command::Help, &["?"]),
+    (Command::Quit, &["q", "ctrl+c"]),
+];
+
+/// Keys each command is bound to in `InputContext::Menu` when the config doesn't change them
+const DEFAULT_MENU_BINDINGS: [(Command, &[&str]); 9] = [
+    (Command::Up, &["k", "up"]),
+    (Command::Down, &["j", "down"]),
+    (Command::NextPage, &["l", "right", "pagedown"]),
+    (Command::PreviousPage, &["h", "left", "pageup"]),
+    (Command::Select, &["space"]),
+    (Command::Confirm, &["enter"]),
+    (Command::Cancel, &["esc", "q"]),
+    (Command::ToggleStats, &["f3"]),
+    (Command::Quit, &["ctrl+c"]),
+];
+
+/// Keys each command is bound to in `InputContext::Text` when the config doesn't change them. Every other key is typed
+const DEFAULT_TEXT_BINDINGS: [(Command, &[&str]); 4] = [
+    (Command::Confirm, &["enter"]),
+    (Command::Cancel, &["esc"]),
+    (Command::ToggleStats, &["f3"]),
+    (Command::Quit, &["ctrl+c"]),
+];
+
+/// A key along with the modifiers held with it
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+
+    /// Returns the binding for `code` pressed with `modifiers`.
+    ///
+    /// Letters are stored lowercase with `SHIFT`, as terminals report shift+k as both `'K'` and `SHIFT`, or just `'K'`.
+    /// `SHIFT` is ignored for other characters, as it's needed to type many of them
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(character) if character.is_uppercase() => KeyBinding {
+                code: KeyCode::Char(character.to_lowercase().next().unwrap_or(character)),
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            KeyCode::Char(character) if !character.is_alphabetic() => KeyBinding {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            code => KeyBinding { code, modifiers }
+        }
+    }
+
+    /// Parses a binding such as `"k"`, `"ctrl+shift+up"` or `"f5"`.
+    ///
+    /// Numpad keys (`"numpad8"`) are the same as the digit keys, as terminals don't tell them apart. Binding a digit
+    /// in `InputContext::Game` turns off counts, so numpad movement isn't read as a count
+    pub fn parse(text: &str) -> Result<KeyBinding> {
+        let invalid = || ConfigError::InvalidValue(format!("\"{}\" is not a key", text));
+
+        // Split off the key before lowercasing, so "K" means shift+k
+        let (modifier_text, key) = match text.rfind('+') {
+            _ if text.ends_with("++") => (&text[..text.len() - 2], "+"),
+            Some(i) if i + 1 < text.len() => (&text[..i], &text[i + 1..]),
+            _ => ("", text)
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_text.split('+').filter(|modifier| !modifier.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid())
+            };
+        }
+
+        let mut characters = key.chars();
+        let code = match (characters.next(), characters.next()) {
+            (Some(character), None) => KeyCode::Char(character),
+            _ => parse_named_key(&key.to_lowercase()).ok_or_else(invalid)?
+        };
+
+        Ok(KeyBinding::new(code, modifiers))
+    }
+
+}
+
+impl From<KeyEvent> for KeyBinding {
+
+    fn from(event: KeyEvent) -> Self {
+        KeyBinding::new(event.code, event.modifiers)
+    }
+
+}
+
+impl fmt::Display for KeyBinding {
+
+    /// Writes the binding the way `parse` reads it, such as `"ctrl+shift+up"`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let modifiers = [(KeyModifiers::CONTROL, "ctrl"), (KeyModifiers::ALT, "alt"), (KeyModifiers::SHIFT, "shift")];
+        for (modifier, name) in modifiers.iter() {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char('+') => write!(f, "plus"),
+            KeyCode::Char(character) => write!(f, "{}", character),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            code => {
+                let name = match code {
+                    KeyCode::Up => "up",
+                    KeyCode::Down => "down",
+                    KeyCode::Left => "left",
+                    KeyCode::Right => "right",
+                    KeyCode::Enter => "enter",
+                    KeyCode::Esc => "esc",
+                    KeyCode::Tab => "tab",
+                    KeyCode::BackTab => "backtab",
+                    KeyCode::Backspace => "backspace",
+                    KeyCode::Delete => "delete",
+                    KeyCode::Insert => "insert",
+                    KeyCode::Home => "home",
+                    KeyCode::End => "end",
+                    KeyCode::PageUp => "pageup",
+                    KeyCode::PageDown => "pagedown",
+                    _ => "null"
+                };
+                write!(f, "{}", name)
+            }
+        }
+    }
+
+}
+
+/// Returns the `KeyCode` of a key with a name longer than 1 character
+fn parse_named_key(name: &str) -> Option<KeyCode> {
+    let code = match name {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        "plus" => KeyCode::Char('+'),
+        _ => {
+            if let Some(number) = name.strip_prefix('f') {
+                return number.parse().ok().filter(|n| (1..=12).contains(n)).map(KeyCode::F);
+            }
+            if let Some(digit) = name.strip_prefix("numpad") {
+                return digit.parse::<u8>().ok().filter(|&n| n <= 9).map(|n| KeyCode::Char((b'0' + n) as char));
+            }
+            return None;
+        }
+    };
+
+    Some(code)
+}
+
+/// Which `Command` each key gives, in each `InputContext`
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: HashMap<InputContext, HashMap<KeyBinding, Command>>,
+}
+
+impl Default for Keymap {
+
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+
+        for &context in InputContext::ALL.iter() {
+            let defaults: &[(Command, &[&str])] = match context {
+                InputContext::Game => &DEFAULT_GAME_BINDINGS,
+                InputContext::Menu => &DEFAULT_MENU_BINDINGS,
+                InputContext::Text => &DEFAULT_TEXT_BINDINGS,
+            };
+
+            let context_bindings: HashMap<KeyBinding, Command> = defaults.iter()
+                .flat_map(|(command, keys)| keys.iter().map(move |key| (key, *command)))
+                .map(|(key, command)| (KeyBinding::parse(key).expect("Default key bindings are valid"), command))
+                .collect();
+            bindings.insert(context, context_bindings);
+        }
+
+        Keymap { bindings }
+    }
+
+}
+
+impl Keymap {
+
+    /// Returns the command `key` is bound to in `context`, or `None` if it isn't bound
+    pub fn command(&self, context: InputContext, key: KeyEvent) -> Option<Command> {
+        self.bindings.get(&context)?.get(&KeyBinding::from(key)).copied()
+    }
+
+    /// Returns `true` if a digit, which could be a numpad key, is bound to a command in `context`
+    pub fn binds_digits(&self, context: InputContext) -> bool {
+        self.bindings.get(&context)
+            .into_iter()
+            .flat_map(|bindings| bindings.keys())
+            .any(|binding| match binding.code {
+                KeyCode::Char(character) => character.is_ascii_digit() && binding.modifiers == KeyModifiers::NONE,
+                _ => false
+            })
+    }
+
+    /// Returns the keys bound to `command` in `context`, written the way the config names them and sorted
+    pub fn keys(&self, context: InputContext, command: Command) -> Vec<String> {
+        let mut keys: Vec<String> = self.bindings.get(&context)
+            .into_iter()
+            .flat_map(|bindings| bindings.iter())
+            .filter(|(_, bound)| **bound == command)
+            .map(|(binding, _)| binding.to_string())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Parses the `[keys]` table of the config, where each command is given a key or a list of keys.
+    /// Keys directly in the table are for `InputContext::Game`, and other contexts have their own table.
+    /// Commands that are left out keep their default keys
+    ///
+    /// ```toml
+    /// [keys]
+    /// up = ["k", "up", "w"]
+    /// quit = "ctrl+q"
+    ///
+    /// [keys.menu]
+    /// confirm = ["enter", "space"]
+    /// ```
+    pub fn parse(table: &Value) -> Result<Keymap> {
+        let table = table.as_table()
+            .ok_or_else(|| ConfigError::InvalidValue("keys should be a table".to_string()))?;
+
+        let mut keymap = Keymap::default();
+
+        for (name, keys) in table {
+            match (name.parse::<InputContext>(), keys) {
+                (Ok(context), Value::Table(context_table)) => {
+                    for (name, keys) in context_table {
+                        keymap.bind(context, name, keys)?;
+                    }
+                },
+                _ => keymap.bind(InputContext::Game, name, keys)?
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// Replaces the keys for the command called `name` in `context` with `keys`, which is a key or a list of keys
+    fn bind(&mut self, context: InputContext, name: &str, keys: &Value) -> Result<()> {
+        let command: Command = name.parse().map_err(ConfigError::InvalidValue)?;
+
+        if !context.has_command(command) {
+            return Err(ConfigError::InvalidValue(format!("{} isn't a {} command", name, context.name())));
+        }
+
+        let keys = match keys {
+            Value::String(key) => vec!(key.as_str()),
+            Value::Array(keys) => keys.iter()
+                .map(|key| key.as_str().ok_or_else(|| ConfigError::InvalidValue(format!("keys for {} should be strings", name))))
+                .collect::<Result<Vec<&str>>>()?,
+            _ => return Err(ConfigError::InvalidValue(format!("keys for {} should be a string or a list", name)))
+        };
+
+        let bindings = self.bindings.entry(context).or_default();
+        bindings.retain(|_, bound| *bound != command);
+        for key in keys {
+            bindings.insert(KeyBinding::parse(key)?, command);
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_binding() -> TestResult {
+        assert_eq!(KeyBinding::parse("k")?, KeyBinding::new(KeyCode::Char('k'), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("Ctrl+Alt+up")?, KeyBinding::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(KeyBinding::parse("f5")?, KeyBinding::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(KeyBinding::parse("numpad8")?, KeyBinding::parse("8")?);
+        assert_eq!(KeyBinding::parse("ctrl++")?, KeyBinding::new(KeyCode::Char('+'), KeyModifiers::CONTROL));
+
+        assert!(KeyBinding::parse("hyper+k").is_err());
+        assert!(KeyBinding::parse("f13").is_err());
+        assert!(KeyBinding::parse("nothing").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_binding() -> TestResult {
+        for text in ["k", "ctrl+shift+up", "shift+k", "f5", "space", "alt+plus", "pagedown"].iter() {
+            assert_eq!(KeyBinding::parse(text)?.to_string(), *text);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shift_letters() -> TestResult {
+        let keymap = Keymap::parse(&"left = \"shift+h\"".parse::<Value>()?)?;
+
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('H'), KeyModifiers::SHIFT)), Some(Command::Left));
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('H'), KeyModifiers::NONE)), Some(Command::Left));
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('h'), KeyModifiers::NONE)), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unbound_keys() {
+        let keymap = Keymap::default();
+
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('k'), KeyModifiers::NONE)), Some(Command::Up));
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('z'), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('k'), KeyModifiers::CONTROL)), None);
+    }
+
+    #[test]
+    fn test_keys_for_command() -> TestResult {
+        let keymap = Keymap::parse(&"up = [\"w\", \"ctrl+k\"]".parse::<Value>()?)?;
+
+        assert_eq!(keymap.keys(InputContext::Game, Command::Up), vec!["ctrl+k", "w"]);
+        assert_eq!(keymap.keys(InputContext::Menu, Command::Up), vec!["k", "up"]);
+        assert!(keymap.keys(InputContext::Text, Command::Up).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_replaces_defaults() -> TestResult {
+        let keymap = Keymap::parse(&"up = [\"w\", \"numpad8\"]".parse::<Value>()?)?;
+
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('w'), KeyModifiers::NONE)), Some(Command::Up));
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('8'), KeyModifiers::NONE)), Some(Command::Up));
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('k'), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.command(InputContext::Game, key(KeyCode::Char('j'), KeyModifiers::NONE)), Some(Command::Down));
+
+        assert!(Keymap::parse(&"fly = \"f\"".parse::<Value>()?).is_err());
+
+        assert!(keymap.binds_digits(InputContext::Game));
+        assert!(!Keymap::default().binds_digits(InputContext::Game));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_contexts() -> TestResult {
+        let keymap = Keymap::parse(&"[menu]\nconfirm = \"y\"".parse::<Value>()?)?;
+        let enter = key(KeyCode::Enter, KeyModifiers::NONE);
+        let y = key(KeyCode::Char('y'), KeyModifiers::NONE);
+
+        assert_eq!(keymap.command(InputContext::Menu, y), Some(Command::Confirm));
+        assert_eq!(keymap.command(InputContext::Menu, enter), None);
+        assert_eq!(keymap.command(InputContext::Game, y), None);
+        assert_eq!(keymap.command(InputContext::Menu, key(KeyCode::Esc, KeyModifiers::NONE)), Some(Command::Cancel));
+
+        // Commands can only be bound in contexts that handle them
+        assert!(Keymap::parse(&"confirm = \"y\"".parse::<Value>()?).is_err());
+        assert!(Keymap::parse(&"[menu]\nexplore = \"o\"".parse::<Value>()?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_defaults_fit_contexts() {
+        let keymap = Keymap::default();
+
+        for (context, bindings) in &keymap.bindings {
+            for command in bindings.values() {
+                assert!(context.has_command(*command), "{:?} is bound in {:?}", command, context);
+            }
+        }
+    }
+}
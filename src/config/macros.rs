@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use crossterm::event::KeyEvent;
+use toml::Value;
+
+use super::{config_dir, ConfigError};
+use super::keymap::KeyBinding;
+use crate::game::{Command, InputContext};
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+/// One step of a macro: a command, and how many times in a row to do it
+pub type MacroStep = (Command, u32);
+
+/// Recorded sequences of commands, each played back by pressing the key it's bound to. Kept in `macros.toml` in the
+/// user's config directory, where each step is a command, with a count in front if it's done more than once
+///
+/// ```toml
+/// f1 = ["right", "3 down", "screenshot"]
+/// "ctrl+f2" = ["explore"]
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Macros {
+    macros: HashMap<KeyBinding, Vec<MacroStep>>,
+    /// File the macros are saved to, or `None` if they aren't saved
+    path: Option<PathBuf>,
+}
+
+impl Macros {
+
+    /// Loads the user's macros, which are saved back to the same file. Returns no macros if there is no macros file
+    pub fn load() -> Result<Macros> {
+        match config_dir() {
+            Some(dir) => Macros::load_from(dir.join("macros.toml")),
+            None => Ok(Macros::default())
+        }
+    }
+
+    /// Loads the macros in the file at `path`, which they are saved back to. Returns no macros if the file doesn't
+    /// exist yet
+    pub fn load_from(path: PathBuf) -> Result<Macros> {
+        let mut macros = if path.exists() {
+            Macros::parse(&fs::read_to_string(&path)?)?
+        } else {
+            Macros::default()
+        };
+        macros.path = Some(path);
+
+        Ok(macros)
+    }
+
+    /// Parses the contents of a macros file. The macros aren't saved anywhere
+    pub fn parse(contents: &str) -> Result<Macros> {
+        let root: Value = contents.parse()?;
+        let table = root.as_table()
+            .ok_or_else(|| ConfigError::InvalidValue("macros should be a table".to_string()))?;
+
+        let mut macros = HashMap::new();
+        for (key, steps) in table {
+            let steps = steps.as_array()
+                .ok_or_else(|| ConfigError::InvalidValue(format!("macro for {} should be a list", key)))?
+                .iter()
+                .map(|step| step.as_str().ok_or_else(|| invalid_step(key)).and_then(|step| parse_step(key, step)))
+                .collect::<Result<Vec<MacroStep>>>()?;
+
+            macros.insert(KeyBinding::parse(key)?, steps);
+        }
+
+        Ok(Macros { macros, path: None })
+    }
+
+    /// Returns the steps of the macro `key` plays, if it plays one
+    pub fn get(&self, key: KeyEvent) -> Option<&[MacroStep]> {
+        self.macros.get(&KeyBinding::from(key)).map(Vec::as_slice)
+    }
+
+    /// Binds `steps` to `key`, replacing any macro it played before. Binding no steps removes the macro
+    pub fn insert(&mut self, key: KeyEvent, steps: Vec<MacroStep>) {
+        if steps.is_empty() {
+            self.macros.remove(&KeyBinding::from(key));
+        } else {
+            self.macros.insert(KeyBinding::from(key), steps);
+        }
+    }
+
+    /// Writes the macros to the file they were loaded from. Does nothing if they weren't loaded from a file
+    pub fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(())
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml())?;
+
+        Ok(())
+    }
+
+    /// Returns the macros the way the macros file has them, sorted by key
+    fn to_toml(&self) -> String {
+        let mut lines: Vec<String> = self.macros.iter()
+            .map(|(key, steps)| {
+                let steps: Vec<String> = steps.iter()
+                    .map(|&(command, count)| match count {
+                        1 => command.name().to_string(),
+                        count => format!("{} {}", count, command.name())
+                    })
+                    .map(|step| Value::String(step).to_string())
+                    .collect();
+
+                format!("{} = [{}]", Value::String(key.to_string()), steps.join(", "))
+            })
+            .collect();
+        lines.sort();
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+}
+
+/// Parses a step of the macro for `key`, like `"down"` or `"3 down"`
+fn parse_step(key: &str, step: &str) -> Result<MacroStep> {
+    let (count, name) = match step.split_once(' ') {
+        Some((count, name)) => (count.parse().map_err(|_| invalid_step(key))?, name),
+        None => (1, step)
+    };
+
+    if count == 0 {
+        return Err(invalid_step(key));
+    }
+
+    let command: Command = name.parse().map_err(ConfigError::InvalidValue)?;
+
+    // Macros are played in the game, so menu commands would do nothing
+    if !InputContext::Game.has_command(command) {
+        return Err(ConfigError::InvalidValue(format!("{} isn't a game command, so can't be in a macro", name)));
+    }
+
+    Ok((command, count))
+}
+
+fn invalid_step(key: &str) -> ConfigError {
+    ConfigError::InvalidValue(format!("steps of the macro for {} should be commands, like \"down\" or \"3 down\"", key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_round_trip() -> TestResult {
+        let contents = "\"ctrl+f2\" = [\"explore\"]\n\"f1\" = [\"right\", \"3 down\", \"screenshot\"]\n";
+        let macros = Macros::parse(contents)?;
+
+        let f1 = KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE);
+        let expected: &[MacroStep] = &[(Command::Right, 1), (Command::Down, 3), (Command::Screenshot, 1)];
+        assert_eq!(macros.get(f1), Some(expected));
+        assert_eq!(macros.to_toml(), contents);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_steps() {
+        assert!(Macros::parse("f1 = [\"fly\"]").is_err());
+        assert!(Macros::parse("f1 = [\"confirm\"]").is_err());
+        assert!(Macros::parse("f1 = [\"0 down\"]").is_err());
+        assert!(Macros::parse("f1 = \"down\"").is_err());
+        assert!(Macros::parse("nothing = [\"down\"]").is_err());
+    }
+}
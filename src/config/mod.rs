@@ -0,0 +1,373 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use toml::Value;
+
+use crate::game::animation_gate::AnimationMode;
+use crate::utility::color_util::ColorDepth;
+use crate::utility::glyph::TileFont;
+use keymap::Keymap;
+use macros::Macros;
+use theme::Theme;
+
+pub mod keymap;
+pub mod macros;
+pub mod theme;
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Environment variable that can point to a config file to use instead of the default location
+const CONFIG_PATH_VAR: &str = "TERMINAL_DUNGEON_CONFIG";
+
+/// Config set by `init`, which `get` returns
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// User settings, loaded from a TOML file
+///
+/// ```toml
+/// [display]
+/// color_depth = "256"  # "truecolor", "256" or "16". Detected from the terminal if left out
+/// theme = "deuteranopia"
+/// tile_font = "box"      # "ascii", "unicode" or "box"
+/// wide_glyphs = false    # Draw double width glyphs as "?", for terminals that get their width wrong
+///
+/// [export]
+/// directory = "screenshots"  # Where screenshots are saved
+///
+/// [gameplay]
+/// move_animation_ms = 80           # How long moving one tile takes to animate. 0 turns it off
+/// animation_mode = "fast-forward"  # "queue" or "fast-forward": what happens to turns taken during animations
+///
+/// [performance]
+/// target_fps = 60    # Most frames drawn each second. 0 draws a frame for every tick and input
+/// tick_rate_ms = 16  # Longest time between ticks when there's no input
+///
+/// [keys]
+/// up = ["k", "up", "w"]
+/// quit = "ctrl+q"
+///
+/// [keys.menu]
+/// confirm = ["enter", "y"]
+/// ```
+#[derive(Debug)]
+pub struct Config {
+    /// Overrides the color depth detected from the terminal
+    pub color_depth: Option<ColorDepth>,
+    /// Name of the theme to start with
+    pub theme: Option<String>,
+    /// Characters map tiles are drawn with
+    pub tile_font: TileFont,
+    /// Whether double width glyphs, like CJK and emoji, are drawn across 2 cells
+    pub wide_glyphs: bool,
+    /// Folder screenshots are saved in
+    pub screenshot_dir: PathBuf,
+    /// How long moving one tile takes to animate
+    pub move_animation: Duration,
+    /// What happens to turns taken while animations play
+    pub animation_mode: AnimationMode,
+    /// Most frames drawn each second, or 0 for no limit
+    pub target_fps: u32,
+    /// Longest time between ticks when there's no input
+    pub tick_rate: Duration,
+    /// Which command each key gives in each input context
+    pub keymap: Keymap,
+    /// Every theme that can be switched between: the built in themes and the user's themes
+    pub themes: Vec<Theme>,
+    /// Macros the user has recorded
+    pub macros: Macros,
+}
+
+impl Default for Config {
+
+    fn default() -> Self {
+        Config {
+            color_depth: None,
+            theme: None,
+            tile_font: TileFont::default(),
+            wide_glyphs: true,
+            screenshot_dir: PathBuf::from("screenshots"),
+            move_animation: Duration::from_millis(80),
+            animation_mode: AnimationMode::default(),
+            target_fps: 60,
+            tick_rate: Duration::from_millis(16),
+            keymap: Keymap::default(),
+            themes: Theme::built_in(),
+            macros: Macros::default(),
+        }
+    }
+
+}
+
+impl Config {
+
+    /// Loads the config from the user's config file, along with the user's themes and macros.
+    /// Returns the default config if there is no config file
+    pub fn load() -> Result<Config> {
+        let mut config = match config_path() {
+            Some(path) if path.exists() => {
+                let contents = fs::read_to_string(&path)?;
+                Self::parse(&contents)?
+            },
+            _ => Config::default()
+        };
+
+        config.themes = Theme::load_all()?;
+        config.macros = Macros::load()?;
+
+        Ok(config)
+    }
+
+    /// Parses a config from the contents of a config file
+    pub fn parse(contents: &str) -> Result<Config> {
+        let root: Value = contents.parse()?;
+
+        let color_depth = match get_str(&root, "display", "color_depth")? {
+            Some(depth) => Some(depth.parse().map_err(ConfigError::InvalidValue)?),
+            None => None
+        };
+
+        let theme = get_str(&root, "display", "theme")?.map(str::to_string);
+
+        let tile_font = match get_str(&root, "display", "tile_font")? {
+            Some(font) => font.parse().map_err(ConfigError::InvalidValue)?,
+            None => TileFont::default()
+        };
+
+        let wide_glyphs = get_bool(&root, "display", "wide_glyphs")?.unwrap_or(true);
+
+        let screenshot_dir = get_str(&root, "export", "directory")?
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Config::default().screenshot_dir);
+
+        let move_animation = match get_integer(&root, "gameplay", "move_animation_ms")? {
+            Some(millis) if millis >= 0 => Duration::from_millis(millis as u64),
+            Some(_) => return Err(ConfigError::InvalidValue("gameplay.move_animation_ms can't be negative".to_string())),
+            None => Config::default().move_animation
+        };
+
+        let animation_mode = match get_str(&root, "gameplay", "animation_mode")? {
+            Some(mode) => mode.parse().map_err(ConfigError::InvalidValue)?,
+            None => AnimationMode::default()
+        };
+
+        let target_fps = match get_integer(&root, "performance", "target_fps")? {
+            Some(fps) if (0..=i64::from(u32::MAX)).contains(&fps) => fps as u32,
+            Some(_) => return Err(ConfigError::InvalidValue("performance.target_fps can't be negative".to_string())),
+            None => Config::default().target_fps
+        };
+
+        let tick_rate = match get_integer(&root, "performance", "tick_rate_ms")? {
+            Some(millis) if millis > 0 => Duration::from_millis(millis as u64),
+            Some(_) => return Err(ConfigError::InvalidValue("performance.tick_rate_ms should be at least 1".to_string())),
+            None => Config::default().tick_rate
+        };
+
+        let keymap = match root.get("keys") {
+            Some(table) => Keymap::parse(table)?,
+            None => Keymap::default()
+        };
+
+        Ok(Config {
+            color_depth,
+            theme,
+            tile_font,
+            wide_glyphs,
+            screenshot_dir,
+            move_animation,
+            animation_mode,
+            target_fps,
+            tick_rate,
+            keymap,
+            ..Config::default()
+        })
+    }
+
+    /// Returns the index in `themes` of the theme to start with. Falls back to the first theme if the configured
+    /// theme doesn't exist
+    pub fn starting_theme(&self) -> usize {
+        self.theme.as_ref()
+            .and_then(|name| self.themes.iter().position(|theme| &theme.name == name))
+            .unwrap_or(0)
+    }
+
+    /// Returns the color depth to draw with, detecting it from the terminal if the config doesn't set one
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color_depth.unwrap_or_else(ColorDepth::detect)
+    }
+
+}
+
+/// Sets the config returned by `get`. Does nothing if the config was already set
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// Returns the config set by `init`, or the default config if `init` was never called
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Returns path of the user's config file: `$TERMINAL_DUNGEON_CONFIG`, or `config.toml` in the
+/// `terminal-dungeon` folder of the user's config directory
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_VAR) {
+        return Some(PathBuf::from(path));
+    }
+
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Returns the `terminal-dungeon` folder in the user's config directory (`$XDG_CONFIG_HOME` or `~/.config`)
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+
+    Some(base.join("terminal-dungeon"))
+}
+
+/// Returns the string at `section.key`, or `None` if it isn't set
+fn get_str<'a>(root: &'a Value, section: &str, key: &str) -> Result<Option<&'a str>> {
+    match root.get(section).and_then(|table| table.get(key)) {
+        Some(value) => value.as_str()
+            .map(Some)
+            .ok_or_else(|| ConfigError::InvalidValue(format!("{}.{} should be a string", section, key))),
+        None => Ok(None)
+    }
+}
+
+/// Returns the bool at `section.key`, or `None` if it isn't set
+fn get_bool(root: &Value, section: &str, key: &str) -> Result<Option<bool>> {
+    match root.get(section).and_then(|table| table.get(key)) {
+        Some(value) => value.as_bool()
+            .map(Some)
+            .ok_or_else(|| ConfigError::InvalidValue(format!("{}.{} should be true or false", section, key))),
+        None => Ok(None)
+    }
+}
+
+/// Returns the integer at `section.key`, or `None` if it isn't set
+fn get_integer(root: &Value, section: &str, key: &str) -> Result<Option<i64>> {
+    match root.get(section).and_then(|table| table.get(key)) {
+        Some(value) => value.as_integer()
+            .map(Some)
+            .ok_or_else(|| ConfigError::InvalidValue(format!("{}.{} should be a whole number", section, key))),
+        None => Ok(None)
+    }
+}
+
+/// Error when loading the config
+#[derive(Debug)]
+pub enum ConfigError {
+    IoError(io::Error),
+    ParseError(toml::de::Error),
+    InvalidValue(String),
+}
+
+impl Error for ConfigError {}
+
+impl fmt::Display for ConfigError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::IoError(error) => write!(f, "Could not read config file: {}", error),
+            ConfigError::ParseError(error) => write!(f, "Could not parse config file: {}", error),
+            ConfigError::InvalidValue(message) => write!(f, "Invalid value in config file: {}", message),
+        }
+    }
+
+}
+
+impl From<io::Error> for ConfigError {
+
+    fn from(error: io::Error) -> Self {
+        ConfigError::IoError(error)
+    }
+
+}
+
+impl From<toml::de::Error> for ConfigError {
+
+    fn from(error: toml::de::Error) -> Self {
+        ConfigError::ParseError(error)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+
+    #[test]
+    fn test_empty_config() -> TestResult {
+        let config = Config::parse("")?;
+        assert_eq!(config.color_depth, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_theme() -> TestResult {
+        let config = Config::parse("[display]\ntheme = \"protanopia\"")?;
+        assert_eq!(config.themes[config.starting_theme()].name, "protanopia");
+
+        let config = Config::parse("[display]\ntheme = \"missing\"")?;
+        assert_eq!(config.starting_theme(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_color_depth() -> TestResult {
+        let config = Config::parse("[display]\ncolor_depth = \"256\"")?;
+        assert_eq!(config.color_depth, Some(ColorDepth::Indexed256));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glyph_options() -> TestResult {
+        let config = Config::parse("[display]\ntile_font = \"unicode\"\nwide_glyphs = false")?;
+        assert_eq!(config.tile_font, TileFont::Unicode);
+        assert!(!config.wide_glyphs);
+
+        assert!(Config::parse("[display]\nwide_glyphs = \"no\"").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_animation_options() -> TestResult {
+        let config = Config::parse("[gameplay]\nmove_animation_ms = 0\nanimation_mode = \"fast-forward\"")?;
+        assert_eq!(config.move_animation, Duration::from_millis(0));
+        assert_eq!(config.animation_mode, AnimationMode::FastForward);
+
+        assert!(Config::parse("[gameplay]\nmove_animation_ms = -5").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_performance_options() -> TestResult {
+        let config = Config::parse("[performance]\ntarget_fps = 0\ntick_rate_ms = 50")?;
+        assert_eq!(config.target_fps, 0);
+        assert_eq!(config.tick_rate, Duration::from_millis(50));
+
+        assert!(Config::parse("[performance]\ntarget_fps = -1").is_err());
+        assert!(Config::parse("[performance]\ntick_rate_ms = 0").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_color_depth() {
+        assert!(Config::parse("[display]\ncolor_depth = \"lots\"").is_err());
+        assert!(Config::parse("[display]\ncolor_depth = 256").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fs;
+use toml::Value;
+use tui::style::Color;
+
+use super::{ConfigError, config_dir};
+use crate::utility::color_util;
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Themes that ship with the game, in the order they are cycled through
+const BUILT_IN_THEMES: [&str; 4] = [
+    include_str!("../../themes/default.toml"),
+    include_str!("../../themes/high-contrast.toml"),
+    include_str!("../../themes/deuteranopia.toml"),
+    include_str!("../../themes/protanopia.toml"),
+];
+
+/// What something on screen is, which decides what color it's drawn with
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ColorRole {
+    Wall,
+    Floor,
+    Player,
+    Hostile,
+    Friendly,
+    Item,
+    Highlight,
+}
+
+impl ColorRole {
+
+    const ALL: [ColorRole; 7] = [
+        ColorRole::Wall,
+        ColorRole::Floor,
+        ColorRole::Player,
+        ColorRole::Hostile,
+        ColorRole::Friendly,
+        ColorRole::Item,
+        ColorRole::Highlight,
+    ];
+
+    /// Name of the role in theme files
+    fn key(self) -> &'static str {
+        match self {
+            ColorRole::Wall => "wall",
+            ColorRole::Floor => "floor",
+            ColorRole::Player => "player",
+            ColorRole::Hostile => "hostile",
+            ColorRole::Friendly => "friendly",
+            ColorRole::Item => "item",
+            ColorRole::Highlight => "highlight",
+        }
+    }
+
+}
+
+/// Colors for one `ColorRole`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RoleColors {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Default for RoleColors {
+
+    fn default() -> Self {
+        RoleColors { foreground: Color::Reset, background: Color::Reset }
+    }
+
+}
+
+/// Maps each `ColorRole` to the colors it's drawn with
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    colors: HashMap<ColorRole, RoleColors>,
+}
+
+impl Default for Theme {
+
+    fn default() -> Self {
+        Theme::parse_given(BUILT_IN_THEMES[0]).expect("Built in default theme is valid")
+    }
+
+}
+
+impl Theme {
+
+    /// Returns the colors for `role`
+    pub fn colors(&self, role: ColorRole) -> RoleColors {
+        self.colors.get(&role).copied().unwrap_or_default()
+    }
+
+    /// Parses a theme from the contents of a theme file. Roles the file leaves out use the default theme's colors
+    ///
+    /// ```toml
+    /// name = "my theme"
+    ///
+    /// [colors]
+    /// wall = { foreground = "black", background = "#e0e0e0" }
+    /// hostile = "lightred"  # Just the foreground, the background is the terminal's default
+    /// ```
+    pub fn parse(contents: &str) -> Result<Theme> {
+        let mut theme = Theme::parse_given(contents)?;
+
+        // Fill in what is missing from the default theme, which has every role
+        let default = Theme::default();
+        for &role in ColorRole::ALL.iter() {
+            theme.colors.entry(role).or_insert_with(|| default.colors(role));
+        }
+
+        Ok(theme)
+    }
+
+    /// Parses a theme with only the roles given in `contents`
+    fn parse_given(contents: &str) -> Result<Theme> {
+        let root: Value = contents.parse()?;
+
+        let name = root.get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ConfigError::InvalidValue("theme is missing a name".to_string()))?
+            .to_string();
+
+        let mut colors = HashMap::new();
+        if let Some(table) = root.get("colors") {
+            for &role in ColorRole::ALL.iter() {
+                if let Some(value) = table.get(role.key()) {
+                    colors.insert(role, parse_role_colors(role, value)?);
+                }
+            }
+        }
+
+        Ok(Theme { name, colors })
+    }
+
+    /// Returns the themes that ship with the game
+    pub fn built_in() -> Vec<Theme> {
+        BUILT_IN_THEMES.iter()
+            .map(|contents| Theme::parse(contents).expect("Built in themes are valid"))
+            .collect()
+    }
+
+    /// Returns the built in themes, followed by the user's themes in the `themes` folder of their config directory.
+    /// User themes with the same name as a built in theme replace it
+    pub fn load_all() -> Result<Vec<Theme>> {
+        let mut themes = Theme::built_in();
+
+        let themes_dir = match config_dir() {
+            Some(dir) if dir.join("themes").is_dir() => dir.join("themes"),
+            _ => return Ok(themes)
+        };
+
+        let mut paths: Vec<_> = fs::read_dir(themes_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let theme = Theme::parse(&fs::read_to_string(&path)?)?;
+
+            match themes.iter_mut().find(|existing| existing.name == theme.name) {
+                Some(existing) => *existing = theme,
+                None => themes.push(theme)
+            }
+        }
+
+        Ok(themes)
+    }
+
+}
+
+/// Parses either a foreground color string, or a table with a `foreground` and `background`
+fn parse_role_colors(role: ColorRole, value: &Value) -> Result<RoleColors> {
+    let parse = |text: Option<&Value>| -> Result<Option<Color>> {
+        match text {
+            Some(Value::String(text)) => color_util::parse_color(text)
+                .map(Some)
+                .ok_or_else(|| ConfigError::InvalidValue(format!("\"{}\" for {} is not a color", text, role.key()))),
+            Some(_) => Err(ConfigError::InvalidValue(format!("colors for {} should be strings", role.key()))),
+            None => Ok(None)
+        }
+    };
+
+    let (foreground, background) = match value {
+        Value::Table(table) => (parse(table.get("foreground"))?, parse(table.get("background"))?),
+        value => (parse(Some(value))?, None)
+    };
+
+    Ok(RoleColors {
+        foreground: foreground.unwrap_or(Color::Reset),
+        background: background.unwrap_or(Color::Reset),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+
+    #[test]
+    fn test_built_in_themes() {
+        for theme in Theme::built_in() {
+            for &role in ColorRole::ALL.iter() {
+                assert!(theme.colors.contains_key(&role), "{} is missing {:?}", theme.name, role);
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_theme() -> TestResult {
+        let theme = Theme::parse("name = \"mine\"\n[colors]\nhostile = \"#ff0000\"")?;
+
+        let hostile = theme.colors(ColorRole::Hostile);
+        assert_eq!(hostile.foreground, Color::Rgb(255, 0, 0));
+        assert_eq!(hostile.background, Color::Reset);
+
+        // Taken from the default theme
+        assert_eq!(theme.colors(ColorRole::Wall), Theme::default().colors(ColorRole::Wall));
+
+        // Even when the user's theme replaces the default one
+        let theme = Theme::parse("name = \"default\"\n[colors]\nhostile = \"#ff0000\"")?;
+        assert_eq!(theme.colors(ColorRole::Wall), Theme::default().colors(ColorRole::Wall));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_theme() {
+        assert!(Theme::parse("[colors]\nwall = \"red\"").is_err());
+        assert!(Theme::parse("name = \"bad\"\n[colors]\nwall = \"not a color\"").is_err());
+        assert!(Theme::parse("name = \"bad\"\n[colors]\nwall = { foreground = 3 }").is_err());
+    }
+}
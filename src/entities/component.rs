@@ -1,7 +1,11 @@
 use specs::{HashMapStorage, VecStorage, Component, Entity};
+use std::collections::VecDeque;
 use splines::{Spline, Interpolation, Key};
 use std::time::Duration;
 use tui::style::{Color, Modifier};
+use crate::config::theme::ColorRole;
+use crate::utility::{color_util, conversions};
+use crate::utility::icon_spline::IconSpline;
 
 extern crate nalgebra as na;
 use na::Vector2;
@@ -14,6 +18,17 @@ pub struct Position {
     pub vec2: Vector2<i32>
 }
 
+impl Position {
+    /// Returns where the entity is drawn, which is somewhere along its `movement` while it's moving. Anything that
+    /// follows what the player sees, like cameras and sight, goes by this rather than `vec2`
+    pub fn drawn(&self, movement: Option<&PositionAnimation>, progress: Option<&AnimationProgress>) -> Vector2<i32> {
+        match (movement, progress) {
+            (Some(movement), Some(progress)) => movement.sample(progress.fraction()),
+            _ => self.vec2
+        }
+    }
+}
+
 /// How an entitiy appears in the character-based world
 /// Are fully opaque
 #[derive(Component, Debug)]
@@ -22,7 +37,9 @@ pub struct Appearance {
     pub icon: char,
     pub foreground: Color,
     pub background: Color,
-    pub modifiers: Vec<Modifier>
+    pub modifiers: Vec<Modifier>,
+    /// When set, the current theme's colors for this role are used instead of `foreground` and `background`
+    pub role: Option<ColorRole>
 }
 
 /// How an entity affects entities below it when rendering
@@ -42,10 +59,82 @@ pub struct Follow {
     pub offset: Vector2<i32>,
 }
 
-/// Camera that draws to a canvas
+/// Area of the screen a `Camera` draws into. Regions are drawn in the order they are declared, so later regions
+/// appear on top of earlier ones
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ScreenRegion {
+    /// The whole play area
+    Main,
+    /// Small overview in the top right corner
+    Minimap,
+}
+
+/// Camera that draws to the `TextCanvas` on the same entity
+///
+/// The default camera draws to `ScreenRegion::Main` and stays centered on what it follows
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct Camera {
+    /// Where on screen this camera's canvas is drawn
+    pub region: ScreenRegion,
+    /// Width and height in world tiles of each canvas cell. 1 draws every tile, larger values zoom out
+    pub scale: usize,
+    /// Half the width and height of a box around the camera's center that what it follows can move in without the
+    /// camera scrolling
+    pub dead_zone: Vector2<i32>,
+    /// Stops the view from showing past the edge of the map, when the map is larger than the view
+    pub clamp_to_map: bool,
+    /// How quickly the camera catches up to its target, as an exponential rate per second: each tick closes
+    /// `1 - e^(-smoothing * seconds)` of the distance left, so at 1.0 about 63% is closed each second, and higher
+    /// values follow more tightly. Snaps to the target when `None`
+    pub smoothing: Option<f32>,
+    /// Sub-tile position of the camera, which smoothing moves a little each tick. `None` until the camera is first
+    /// placed, when it's put straight onto its target
+    pub focus: Option<Vector2<f32>>,
+}
+
+impl Default for Camera {
+
+    fn default() -> Self {
+        Camera {
+            region: ScreenRegion::Main,
+            scale: 1,
+            dead_zone: Vector2::new(0, 0),
+            clamp_to_map: false,
+            smoothing: None,
+            focus: None,
+        }
+    }
+
+}
+
+impl Camera {
+
+    /// Returns how many world tiles the view reaches left of (or above) its center, when drawn onto a canvas
+    /// `canvas_length` cells across (or down). The cell at `canvas_length / 2` is the one the center is drawn in
+    pub fn half_view(&self, canvas_length: usize) -> i32 {
+        conversions::as_i32(canvas_length / 2) * conversions::as_i32(self.scale.max(1))
+    }
+
+}
+
+/// What an entity is called, used instead of describing it by its role
 #[derive(Component, Debug)]
 #[storage(HashMapStorage)]
-pub struct Camera;
+pub struct Name(pub String);
+
+/// Entity the player has targeted with `Command::Target`, which is drawn in reversed video
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct Targeted;
+
+/// Reveals the tiles of the map an entity can see
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct Viewshed {
+    /// How many tiles away the entity can see
+    pub range: i32
+}
 
 /// Entity Movement through `Command`
 #[derive(Component)]
@@ -58,41 +147,125 @@ pub struct CommandResponse;
 pub struct Collision;
 
 /// Component that stores animation progress
-#[derive(Component)]
+#[derive(Component, Clone, Debug)]
 #[storage(VecStorage)]
 pub struct AnimationProgress {
     pub current: Duration,
     pub total: Duration,
 }
 
-/// Animate `Position` using splines
+impl AnimationProgress {
+    /// Create `AnimationProgress` that lasts `total` and hasn't started
+    pub fn new(total: Duration) -> Self {
+        AnimationProgress { current: Duration::from_secs(0), total }
+    }
+
+    /// Returns how far through the animation is, from 0.0 to 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.total.as_nanos() == 0 {
+            return 1.;
+        }
+
+        (self.current.as_secs_f32() / self.total.as_secs_f32()).min(1.)
+    }
+
+    /// Returns `true` if the animation has played all the way through
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.total
+    }
+}
+
+/// Animate where an entity is drawn using splines, alongside an `AnimationProgress`.
+///
+/// The entity's `Position` is where it really is, and should be where the animation ends. Cameras and sight follow
+/// where it's drawn, so what's shown never gets ahead of the entity
 #[derive(Component)]
 #[storage(VecStorage)]
 pub struct PositionAnimation {
     /// Spline used to sample for x position
-    pub x_spline: Spline<f32, i32>,
+    pub x_spline: Spline<f32, f32>,
     /// Spline used to sample for y position
-    pub y_spline: Spline<f32, i32>,
+    pub y_spline: Spline<f32, f32>,
 }
 
 impl PositionAnimation {
     /// Create `PositionAnimation` that uses `interpolation` for entirety of both the x spline and the y spline
-    fn new(start: Vector2<i32>, end: Vector2<i32>, interpolation: Interpolation<f32, i32>) -> Self {
-        let x_start = Key::new(0., start[0], interpolation);
-        let x_end = Key::new(1., end[0], interpolation);
+    pub fn new(start: Vector2<i32>, end: Vector2<i32>, interpolation: Interpolation<f32, f32>) -> Self {
+        let x_start = Key::new(0., start[0] as f32, interpolation);
+        let x_end = Key::new(1., end[0] as f32, interpolation);
         let x_spline = Spline::from_vec(vec![x_start, x_end]);
 
-        let y_start = Key::new(0., start[1], interpolation);
-        let y_end = Key::new(1., end[1], interpolation);
+        let y_start = Key::new(0., start[1] as f32, interpolation);
+        let y_end = Key::new(1., end[1] as f32, interpolation);
         let y_spline = Spline::from_vec(vec![y_start, y_end]);
 
         PositionAnimation { x_spline, y_spline }
     }
 
     /// Create `PositionAnimation` with custom splines for both x and y
-    fn with_splines(x_spline: Spline<f32, i32>, y_spline: Spline<f32, i32>) -> Self {
+    pub fn with_splines(x_spline: Spline<f32, f32>, y_spline: Spline<f32, f32>) -> Self {
         PositionAnimation { x_spline, y_spline }
     }
+
+    /// Returns where the entity is drawn `fraction` (0.0 .. 1.0) of the way through the animation, rounded to the
+    /// nearest tile
+    pub fn sample(&self, fraction: f32) -> Vector2<i32> {
+        let x = self.x_spline.clamped_sample(fraction).unwrap_or(0.);
+        let y = self.y_spline.clamped_sample(fraction).unwrap_or(0.);
+
+        Vector2::new(x.round() as i32, y.round() as i32)
+    }
+}
+
+/// Entity whose animations have to finish before the next turn is taken
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct BlocksInput;
+
+/// Path an entity is walking along, one tile each turn, such as after the player clicks on a tile
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct Travel {
+    /// Tiles left to walk onto, in order
+    pub path: VecDeque<Vector2<i32>>,
+    /// Tile the last step went to. If the entity isn't there, the step was blocked
+    stepping_to: Option<Vector2<i32>>,
+    /// Entities that could already be seen when setting off, so seeing them doesn't stop the walk
+    pub already_seen: Vec<Entity>,
+    pub mode: TravelMode,
+}
+
+/// Where a `Travel` is headed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TravelMode {
+    /// Along a path planned when setting off
+    Path,
+    /// Towards the closest tile that hasn't been seen, planning again each step as more is seen
+    Explore,
+    /// Onwards from the last step, which moved by the offset given, following corridors
+    Run(Vector2<i32>),
+}
+
+impl Travel {
+    /// Create `Travel` that walks onto each tile of `path` in turn
+    pub fn new(path: impl IntoIterator<Item = Vector2<i32>>, already_seen: Vec<Entity>) -> Self {
+        Travel::with_mode(TravelMode::Path, path, already_seen)
+    }
+
+    /// Create `Travel` that starts off along `path` and keeps going as `mode` says
+    pub fn with_mode(mode: TravelMode, path: impl IntoIterator<Item = Vector2<i32>>, already_seen: Vec<Entity>) -> Self {
+        Travel { path: path.into_iter().collect(), stepping_to: None, already_seen, mode }
+    }
+
+    /// Returns the tile to step onto from `position`, or `None` once the path is walked or the last step was blocked
+    pub fn next_step(&mut self, position: Vector2<i32>) -> Option<Vector2<i32>> {
+        if self.stepping_to.is_some_and(|tile| tile != position) {
+            self.path.clear();
+        }
+
+        self.stepping_to = self.path.pop_front();
+        self.stepping_to
+    }
 }
 
 /// Animate `Appearance` using splines
@@ -103,3 +276,70 @@ pub struct AppearanceAnimation {
     pub background_spline: Option<Spline<f32, (u8, u8, u8)>>,
     // TODO step function for icon and modifiers...
 }
+
+/// Turns `modifier` on and off over time, such as to make an entity blink.
+/// Is added on top of the modifiers the entity already has
+#[derive(Component, Debug)]
+#[storage(HashMapStorage)]
+pub struct ModifierAnimation {
+    pub modifier: Modifier,
+    /// How long one cycle of on then off takes
+    pub period: Duration,
+    /// Fraction of each cycle `modifier` is on for (0.0 .. 1.0)
+    pub duty: f32,
+    /// Time since the animation started
+    pub elapsed: Duration,
+}
+
+impl ModifierAnimation {
+    /// Create `ModifierAnimation` that has `modifier` on for half of every `period`
+    pub fn blink(modifier: Modifier, period: Duration) -> Self {
+        ModifierAnimation { modifier, period, duty: 0.5, elapsed: Duration::from_secs(0) }
+    }
+
+    /// Returns `true` if `modifier` should currently be on
+    pub fn is_on(&self) -> bool {
+        if self.period.as_nanos() == 0 {
+            return true;
+        }
+
+        let cycle_progress = (self.elapsed.as_nanos() % self.period.as_nanos()) as f32 / self.period.as_nanos() as f32;
+        cycle_progress < self.duty
+    }
+}
+
+/// Short lived visual effect, such as a spark or part of an explosion. Is drawn above entities, doesn't collide,
+/// and is deleted once `progress` finishes
+#[derive(Component, Clone)]
+#[storage(HashMapStorage)]
+pub struct Particle {
+    /// Icon over the particle's life, sampled from 0.0 to 1.0
+    pub icon: IconSpline,
+    /// Color at the start of the particle's life
+    pub foreground: Color,
+    /// Color the particle fades to by the end of its life
+    pub fade_to: Color,
+    /// Where the particle started, in world space
+    pub origin: Vector2<f32>,
+    /// Tiles moved per second
+    pub velocity: Vector2<f32>,
+    pub progress: AnimationProgress,
+}
+
+impl Particle {
+    /// Returns the icon for how far through its life the particle is
+    pub fn current_icon(&self) -> char {
+        self.icon.clamped_sample(self.progress.fraction()).unwrap_or('*')
+    }
+
+    /// Returns the foreground color for how far through its life the particle is
+    pub fn current_color(&self) -> Color {
+        color_util::lerp(self.foreground, self.fade_to, self.progress.fraction() as f64)
+    }
+
+    /// Returns where the particle is in world space, rounded to the nearest tile
+    pub fn current_position(&self) -> Vector2<i32> {
+        let position = self.origin + self.velocity * self.progress.current.as_secs_f32();
+        position.map(|n| n.round() as i32)
+    }
+}
@@ -0,0 +1,84 @@
+use crate::entities::component::{AnimationProgress, Particle, Position};
+use crate::utility::geometry;
+use crate::utility::icon_spline::IconSpline;
+use specs::{Builder, Entities, LazyUpdate};
+use std::f32::consts::TAU;
+use std::time::Duration;
+use tui::style::Color;
+
+extern crate nalgebra as na;
+use na::Vector2;
+
+/// Spawns particles that move out from `center` in a circle, reaching `radius` tiles away after `duration`
+pub fn ring(entities: &Entities, lazy: &LazyUpdate, center: Vector2<i32>, radius: f32, duration: Duration) {
+    let icon = icon_spline(vec![(0.0, '*'), (0.3, 'o'), (0.8, '.')]);
+
+    // Enough particles to leave no gaps at the ring's full size
+    let count = ((TAU * radius).ceil() as usize).max(8);
+    let speed = radius / duration.as_secs_f32().max(0.001);
+
+    for i in 0..count {
+        let angle = TAU * i as f32 / count as f32;
+        let particle = Particle {
+            icon: icon.clone(),
+            foreground: Color::LightYellow,
+            fade_to: Color::Red,
+            origin: center.map(|n| n as f32),
+            velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+            progress: AnimationProgress::new(duration),
+        };
+        spawn(entities, lazy, particle);
+    }
+}
+
+/// Spawns `count` sparks that fly out from `center` in every direction at different speeds
+pub fn sparks(entities: &Entities, lazy: &LazyUpdate, center: Vector2<i32>, count: usize) {
+    let icon = icon_spline(vec![(0.0, '*'), (0.4, '+'), (0.7, '.')]);
+
+    for i in 0..count {
+        // Golden angle spreads the sparks out without a visible pattern
+        let angle = i as f32 * 2.399_963;
+        let speed = 3. + (i % 3) as f32 * 2.;
+        let particle = Particle {
+            icon: icon.clone(),
+            foreground: Color::White,
+            fade_to: Color::Rgb(201, 27, 0),
+            origin: center.map(|n| n as f32),
+            velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+            progress: AnimationProgress::new(Duration::from_millis(300 + 50 * (i % 4) as u64)),
+        };
+        spawn(entities, lazy, particle);
+    }
+}
+
+/// Spawns a trail along the line from `from` to `to`, such as behind a projectile.
+/// The end at `from` fades first, with each tile lasting `step` longer than the one before it
+pub fn trail(entities: &Entities, lazy: &LazyUpdate, from: Vector2<i32>, to: Vector2<i32>, step: Duration) {
+    let icon = icon_spline(vec![(0.0, '='), (0.5, '-'), (0.8, '.')]);
+
+    let points = geometry::line((from[0], from[1]), (to[0], to[1]));
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let particle = Particle {
+            icon: icon.clone(),
+            foreground: Color::LightCyan,
+            fade_to: Color::Blue,
+            origin: Vector2::new(x as f32, y as f32),
+            velocity: Vector2::new(0., 0.),
+            progress: AnimationProgress::new(step * (i as u32 + 1)),
+        };
+        spawn(entities, lazy, particle);
+    }
+}
+
+/// Creates an entity for `particle`, which is added to the world when the world is next maintained
+fn spawn(entities: &Entities, lazy: &LazyUpdate, particle: Particle) {
+    let position = Position { vec2: particle.current_position() };
+    lazy.create_entity(entities)
+        .with(position)
+        .with(particle)
+        .build();
+}
+
+fn icon_spline(icon_ranges: Vec<(f32, char)>) -> IconSpline {
+    IconSpline::new(icon_ranges).expect("Effects use valid icon ranges")
+}
@@ -1,6 +1,9 @@
 use crate::entities::component::*;
+use crate::utility::text_canvas::TextCanvas;
+use crate::config::theme::ColorRole;
 use specs::{World, WorldExt, Builder, Entity};
-use tui::style::Color;
+use std::time::Duration;
+use tui::style::{Color, Modifier};
 
 extern crate nalgebra as na;
 use na::Vector2;
@@ -11,21 +14,46 @@ pub fn make_player(world: &mut World) -> Entity {
         icon: '@',
         foreground: Color::Cyan,
         background: Color::Reset,
-        modifiers: vec!()
+        modifiers: vec!(),
+        role: Some(ColorRole::Player)
     };
     let command_component = CommandResponse;
     world.create_entity()
          .with(starting_position)
          .with(appearence)
          .with(command_component)
+         .with(Viewshed { range: 10 })
          .build()
 }
 
 pub fn make_camera(target: Entity, world: &mut World) -> Entity {
     let follow = Follow { target, offset: Vector2::new(0, 0) };
-    let camera = Camera;
+    let camera = Camera {
+        dead_zone: Vector2::new(4, 2),
+        clamp_to_map: true,
+        smoothing: Some(12.),
+        ..Camera::default()
+    };
+    world.create_entity()
+        .with(camera)
+        .with(TextCanvas::default())
+        .with(follow)
+        .with(Position { vec2: Vector2::new(0, 0) })
+        .build()
+}
+
+/// Camera in the corner of the screen that shows a zoomed out view around `target`
+pub fn make_minimap_camera(target: Entity, world: &mut World) -> Entity {
+    let follow = Follow { target, offset: Vector2::new(0, 0) };
+    let camera = Camera {
+        region: ScreenRegion::Minimap,
+        scale: 3,
+        clamp_to_map: true,
+        ..Camera::default()
+    };
     world.create_entity()
         .with(camera)
+        .with(TextCanvas::default())
         .with(follow)
         .with(Position { vec2: Vector2::new(0, 0) })
         .build()
@@ -37,7 +65,8 @@ pub fn make_dummy(world: &mut World) -> Entity {
         icon: '@',
         foreground: Color::Green,
         background: Color::Reset,
-        modifiers: vec!()
+        modifiers: vec!(),
+        role: Some(ColorRole::Friendly)
     };
     let collision_component = Collision;
     world.create_entity()
@@ -46,3 +75,21 @@ pub fn make_dummy(world: &mut World) -> Entity {
         .with(collision_component)
         .build()
 }
+
+/// Dummy that blinks to stand out as a threat
+pub fn make_hostile_dummy(world: &mut World) -> Entity {
+    let starting_position = Position { vec2: Vector2::new(12, 6) };
+    let appearence = Appearance {
+        icon: 'g',
+        foreground: Color::Red,
+        background: Color::Reset,
+        modifiers: vec!(Modifier::BOLD),
+        role: Some(ColorRole::Hostile)
+    };
+    world.create_entity()
+        .with(starting_position)
+        .with(appearence)
+        .with(Collision)
+        .with(ModifierAnimation::blink(Modifier::HIDDEN, Duration::from_millis(1000)))
+        .build()
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::game::{Command, GameTick};
+
+/// What to do with a turn taken while animations that block input are still playing
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum AnimationMode {
+    /// Hold on to the command until the animations finish
+    #[default]
+    Queue,
+    /// Finish the animations straight away, then take the turn
+    FastForward,
+}
+
+impl FromStr for AnimationMode {
+
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "queue" => Ok(AnimationMode::Queue),
+            "fast-forward" => Ok(AnimationMode::FastForward),
+            other => Err(format!("Unknown animation mode \"{}\", expected queue or fast-forward", other))
+        }
+    }
+
+}
+
+/// Holds back turns while animations play, so a turn never starts from a state that is still being drawn
+#[derive(Debug, Default)]
+pub struct AnimationGate {
+    mode: AnimationMode,
+    /// Turns waiting for animations to finish, oldest first
+    queued: VecDeque<Command>,
+}
+
+impl AnimationGate {
+
+    pub fn new(mode: AnimationMode) -> Self {
+        AnimationGate { mode, queued: VecDeque::new() }
+    }
+
+    /// Returns the tick the game should run this frame, given `tick` from the player and whether animations that
+    /// block input are `animating`. The returned `bool` is `true` if the running animations must be finished before
+    /// running the tick.
+    ///
+    /// Commands that aren't turns, like quitting, are never held back
+    pub fn filter(&mut self, tick: GameTick, animating: bool) -> (GameTick, bool) {
+        match tick {
+            GameTick::Command(deltatime, command) if command.is_turn() => {
+                if !animating && self.queued.is_empty() {
+                    return (tick, false);
+                }
+
+                match self.mode {
+                    AnimationMode::Queue => {
+                        self.queued.push_back(command);
+                        (self.next_queued(deltatime, animating), false)
+                    },
+                    AnimationMode::FastForward => {
+                        self.queued.clear();
+                        (tick, animating)
+                    }
+                }
+            },
+            GameTick::Tick(deltatime) => (self.next_queued(deltatime, animating), false),
+            _ => (tick, false)
+        }
+    }
+
+    /// Returns the number of turns waiting for animations to finish
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Returns the oldest queued turn if nothing is animating, otherwise an ordinary tick
+    fn next_queued(&mut self, deltatime: Duration, animating: bool) -> GameTick {
+        match self.queued.front() {
+            Some(&command) if !animating => {
+                self.queued.pop_front();
+                GameTick::Command(deltatime, command)
+            },
+            _ => GameTick::Tick(deltatime)
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn command(command: Command) -> GameTick {
+        GameTick::Command(Duration::from_millis(10), command)
+    }
+
+    fn tick() -> GameTick {
+        GameTick::Tick(Duration::from_millis(10))
+    }
+
+    #[test]
+    fn test_passes_through_when_idle() {
+        let mut gate = AnimationGate::new(AnimationMode::Queue);
+        assert_eq!(gate.filter(command(Command::Up), false), (command(Command::Up), false));
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut gate = AnimationGate::new(AnimationMode::Queue);
+
+        assert_eq!(gate.filter(command(Command::Up), true), (tick(), false));
+        assert_eq!(gate.filter(command(Command::Left), true), (tick(), false));
+        assert_eq!(gate.queued(), 2);
+
+        // Turns come back out in order once the animations are done
+        assert_eq!(gate.filter(tick(), false), (command(Command::Up), false));
+        assert_eq!(gate.filter(command(Command::Down), false), (command(Command::Left), false));
+        assert_eq!(gate.filter(tick(), false), (command(Command::Down), false));
+        assert_eq!(gate.queued(), 0);
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        let mut gate = AnimationGate::new(AnimationMode::FastForward);
+
+        assert_eq!(gate.filter(command(Command::Up), true), (command(Command::Up), true));
+        assert_eq!(gate.filter(command(Command::Up), false), (command(Command::Up), false));
+    }
+
+    #[test]
+    fn test_quit_is_not_held_back() {
+        let mut gate = AnimationGate::new(AnimationMode::Queue);
+        gate.filter(command(Command::Up), true);
+
+        assert_eq!(gate.filter(command(Command::Quit), true), (command(Command::Quit), false));
+    }
+}
@@ -1,11 +1,33 @@
 use std::time::Duration;
 use std::fmt::Debug;
+use crossterm::event::KeyEvent;
+use std::str::FromStr;
 
 /// Possible actions the player gives the games
-#[derive(Copy, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum GameTick {
     Tick(Duration),
-    Command(Duration, Command)
+    Command(Duration, Command),
+    /// `Command` to do the given number of times in a row, typed with a count before it like `5j`
+    Repeat(Duration, Command, u32),
+    Mouse(Duration, MouseInput),
+    /// Key that isn't bound to a command, pressed while typing text in `InputContext::Text`
+    Key(Duration, KeyEvent)
+}
+
+impl GameTick {
+
+    /// Returns the time passed since the last tick
+    pub fn deltatime(&self) -> Duration {
+        match self {
+            Self::Tick(deltatime) => *deltatime,
+            Self::Command(deltatime, _) => *deltatime,
+            Self::Repeat(deltatime, _, _) => *deltatime,
+            Self::Mouse(deltatime, _) => *deltatime,
+            Self::Key(deltatime, _) => *deltatime
+        }
+    }
+
 }
 
 impl Default for GameTick {
@@ -16,15 +38,24 @@ impl Default for GameTick {
 
 }
 
-impl Clone for GameTick {
-
-    fn clone(&self) -> Self {
-        match self {
-            Self::Tick(dur) => GameTick::Tick(*dur),
-            Self::Command(dur, command) => GameTick::Command(*dur, *command)
-        }
-    }
+/// Something done with the mouse, at a cell of the terminal
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct MouseInput {
+    pub action: MouseAction,
+    /// Column of the terminal, counting from 0 on the left
+    pub column: u16,
+    /// Row of the terminal, counting from 0 at the top
+    pub row: u16,
+}
 
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum MouseAction {
+    /// Left button pressed
+    Click,
+    /// Pointing at a cell without clicking it, such as by dragging or right clicking
+    Hover,
+    ScrollUp,
+    ScrollDown,
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
@@ -33,5 +64,195 @@ pub enum Command {
     Down,
     Left,
     Right,
-    Quit
+    /// Switch to the next color theme
+    NextTheme,
+    /// Save what the main camera shows to files
+    Screenshot,
+    Quit,
+    /// Accept what is chosen in a menu
+    Confirm,
+    /// Back out of a menu
+    Cancel,
+    /// Mark or unmark the highlighted item in a menu
+    Select,
+    NextPage,
+    PreviousPage,
+    /// Take the last turn again
+    Repeat,
+    /// Keep walking to the closest place that hasn't been explored
+    Explore,
+    /// Walk to the tile the mouse last pointed at
+    TravelTo,
+    /// Keep moving in a direction, following corridors, until something interesting is reached
+    RunUp,
+    RunDown,
+    RunLeft,
+    RunRight,
+    /// Start recording a macro, choosing the key it's played with, or finish recording it
+    RecordMacro,
+    /// Show or hide the overlay with frame timings
+    ToggleStats,
+    /// Type a name for the player
+    Name,
+    /// List the keys bound to each command
+    Help,
+    /// Target the next thing in view, closest first
+    Target,
+    /// Shoot at the target
+    Fire
+}
+
+impl FromStr for Command {
+
+    type Err = String;
+
+    /// Parses the name of a command as used in the config, such as `"next_theme"`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Command::Up),
+            "down" => Ok(Command::Down),
+            "left" => Ok(Command::Left),
+            "right" => Ok(Command::Right),
+            "next_theme" => Ok(Command::NextTheme),
+            "screenshot" => Ok(Command::Screenshot),
+            "quit" => Ok(Command::Quit),
+            "confirm" => Ok(Command::Confirm),
+            "cancel" => Ok(Command::Cancel),
+            "select" => Ok(Command::Select),
+            "next_page" => Ok(Command::NextPage),
+            "previous_page" => Ok(Command::PreviousPage),
+            "repeat" => Ok(Command::Repeat),
+            "explore" => Ok(Command::Explore),
+            "travel" => Ok(Command::TravelTo),
+            "run_up" => Ok(Command::RunUp),
+            "run_down" => Ok(Command::RunDown),
+            "run_left" => Ok(Command::RunLeft),
+            "run_right" => Ok(Command::RunRight),
+            "record_macro" => Ok(Command::RecordMacro),
+            "toggle_stats" => Ok(Command::ToggleStats),
+            "name" => Ok(Command::Name),
+            "help" => Ok(Command::Help),
+            "target" => Ok(Command::Target),
+            "fire" => Ok(Command::Fire),
+            other => Err(format!("Unknown command \"{}\"", other))
+        }
+    }
+
+}
+
+impl Command {
+
+    /// Returns the name of the command as used in the config, such as `"next_theme"`
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Up => "up",
+            Command::Down => "down",
+            Command::Left => "left",
+            Command::Right => "right",
+            Command::NextTheme => "next_theme",
+            Command::Screenshot => "screenshot",
+            Command::Quit => "quit",
+            Command::Confirm => "confirm",
+            Command::Cancel => "cancel",
+            Command::Select => "select",
+            Command::NextPage => "next_page",
+            Command::PreviousPage => "previous_page",
+            Command::Repeat => "repeat",
+            Command::Explore => "explore",
+            Command::TravelTo => "travel",
+            Command::RunUp => "run_up",
+            Command::RunDown => "run_down",
+            Command::RunLeft => "run_left",
+            Command::RunRight => "run_right",
+            Command::RecordMacro => "record_macro",
+            Command::ToggleStats => "toggle_stats",
+            Command::Name => "name",
+            Command::Help => "help",
+            Command::Target => "target",
+            Command::Fire => "fire",
+        }
+    }
+
+    /// Returns `true` if the command takes a turn in the game world, rather than only affecting the interface
+    pub fn is_turn(self) -> bool {
+        matches!(self, Command::Up | Command::Down | Command::Left | Command::Right)
+    }
+
+    /// Returns the movement command a run command starts off moving with, or `None` if it isn't a run command
+    pub fn run_direction(self) -> Option<Command> {
+        match self {
+            Command::RunUp => Some(Command::Up),
+            Command::RunDown => Some(Command::Down),
+            Command::RunLeft => Some(Command::Left),
+            Command::RunRight => Some(Command::Right),
+            _ => None
+        }
+    }
+
+}
+
+/// Set of key bindings a view uses. The view on top decides which set keys are looked up in
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum InputContext {
+    /// Moving around the world
+    #[default]
+    Game,
+    /// Menus and lists, such as an inventory
+    Menu,
+    /// Typing text, such as a name. Keys that aren't bound to a command are passed on as they are
+    Text,
+}
+
+impl InputContext {
+
+    pub const ALL: [InputContext; 3] = [InputContext::Game, InputContext::Menu, InputContext::Text];
+
+    /// Returns the commands views using this context handle. Only these can be bound to keys in it, so a command
+    /// meant for a menu never reaches the game
+    pub fn commands(self) -> &'static [Command] {
+        match self {
+            InputContext::Game => &[
+                Command::Up, Command::Down, Command::Left, Command::Right,
+                Command::RunUp, Command::RunDown, Command::RunLeft, Command::RunRight,
+                Command::NextTheme, Command::Screenshot, Command::Repeat, Command::Explore, Command::TravelTo,
+                Command::Target, Command::Fire, Command::RecordMacro, Command::ToggleStats, Command::Name,
+                Command::Help, Command::Quit,
+            ],
+            InputContext::Menu => &[
+                Command::Up, Command::Down, Command::NextPage, Command::PreviousPage,
+                Command::Select, Command::Confirm, Command::Cancel, Command::ToggleStats, Command::Quit,
+            ],
+            InputContext::Text => &[Command::Confirm, Command::Cancel, Command::ToggleStats, Command::Quit],
+        }
+    }
+
+    /// Returns `true` if `command` is one of the commands of this context
+    pub fn has_command(self, command: Command) -> bool {
+        self.commands().contains(&command)
+    }
+
+    /// Returns the name of the context as used in the config, such as `"menu"`
+    pub fn name(self) -> &'static str {
+        match self {
+            InputContext::Game => "game",
+            InputContext::Menu => "menu",
+            InputContext::Text => "text",
+        }
+    }
+
+}
+
+impl FromStr for InputContext {
+
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "game" => Ok(InputContext::Game),
+            "menu" => Ok(InputContext::Menu),
+            "text" => Ok(InputContext::Text),
+            other => Err(format!("Unknown input context \"{}\"", other))
+        }
+    }
+
 }
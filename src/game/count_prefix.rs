@@ -0,0 +1,153 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use super::Command;
+
+/// Largest count that can be typed. Typing more digits keeps it here
+const MAX_COUNT: u32 = 999;
+
+/// Keeps track of vi style counts typed before a command, like the `5` in `5j`, and of the last turn taken so
+/// `Command::Repeat` can take it again
+#[derive(Default, Debug)]
+pub struct CountPrefix {
+    /// Count typed so far, if any digits have been typed since the last command
+    count: Option<u32>,
+    /// The last command that took a turn, and the number of times it was done
+    last: Option<(Command, u32)>,
+}
+
+impl CountPrefix {
+
+    /// Handles a key that isn't bound to a command. Digits add to the count, and anything else, such as escape,
+    /// cancels it. Returns `true` if the key was a digit of the count
+    pub fn push_key(&mut self, key: KeyEvent) -> bool {
+        let unmodified = key.modifiers - KeyModifiers::SHIFT == KeyModifiers::NONE;
+        let digit = match key.code {
+            KeyCode::Char(character) if unmodified => character.to_digit(10),
+            _ => None
+        };
+
+        match digit {
+            // A count can't start with 0
+            Some(0) if self.count.is_none() => {
+                self.cancel();
+                false
+            },
+            Some(digit) => {
+                let count = self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                self.count = Some(count.min(MAX_COUNT));
+                true
+            },
+            None => {
+                self.cancel();
+                false
+            }
+        }
+    }
+
+    /// Returns `command` along with how many times to do it, using up the count. `Command::Repeat` gives the last
+    /// turn instead, done the same number of times unless a new count was typed. Returns `None` if there is nothing
+    /// to repeat
+    ///
+    /// Only turns are done more than once. Other commands, like exploring, use up the count but are done once
+    pub fn command(&mut self, command: Command) -> Option<(Command, u32)> {
+        let count = self.count.take();
+
+        let (command, count) = match command {
+            Command::Repeat => {
+                let (last_command, last_count) = self.last?;
+                (last_command, count.unwrap_or(last_count))
+            },
+            command if command.is_turn() => (command, count.unwrap_or(1)),
+            command => (command, 1)
+        };
+
+        if command.is_turn() {
+            self.last = Some((command, count));
+        }
+        Some((command, count))
+    }
+
+    /// Forgets the count typed so far
+    pub fn cancel(&mut self) {
+        self.count = None;
+    }
+
+    /// Returns the count typed so far
+    pub fn pending(&self) -> Option<u32> {
+        self.count
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    /// Types each character of `digits`
+    fn type_count(prefix: &mut CountPrefix, digits: &str) {
+        for digit in digits.chars() {
+            prefix.push_key(key(KeyCode::Char(digit)));
+        }
+    }
+
+    #[test]
+    fn test_count() {
+        let mut prefix = CountPrefix::default();
+        type_count(&mut prefix, "12");
+
+        assert_eq!(prefix.pending(), Some(12));
+        assert_eq!(prefix.command(Command::Down), Some((Command::Down, 12)));
+        assert_eq!(prefix.command(Command::Down), Some((Command::Down, 1)));
+    }
+
+    #[test]
+    fn test_leading_zero_and_limit() {
+        let mut prefix = CountPrefix::default();
+
+        assert!(!prefix.push_key(key(KeyCode::Char('0'))));
+        assert_eq!(prefix.pending(), None);
+
+        type_count(&mut prefix, "10000");
+        assert_eq!(prefix.pending(), Some(MAX_COUNT));
+    }
+
+    #[test]
+    fn test_escape_cancels() {
+        let mut prefix = CountPrefix::default();
+        type_count(&mut prefix, "5");
+
+        assert!(!prefix.push_key(key(KeyCode::Esc)));
+        assert_eq!(prefix.command(Command::Left), Some((Command::Left, 1)));
+    }
+
+    #[test]
+    fn test_repeat() {
+        let mut prefix = CountPrefix::default();
+        assert_eq!(prefix.command(Command::Repeat), None);
+
+        type_count(&mut prefix, "3");
+        prefix.command(Command::Up);
+        assert_eq!(prefix.command(Command::Repeat), Some((Command::Up, 3)));
+
+        // A count before repeating replaces the old one
+        type_count(&mut prefix, "2");
+        assert_eq!(prefix.command(Command::Repeat), Some((Command::Up, 2)));
+        assert_eq!(prefix.command(Command::Repeat), Some((Command::Up, 2)));
+
+        // Only turns are repeated
+        prefix.command(Command::NextTheme);
+        assert_eq!(prefix.command(Command::Repeat), Some((Command::Up, 2)));
+    }
+
+    #[test]
+    fn test_count_before_non_turn() {
+        let mut prefix = CountPrefix::default();
+        type_count(&mut prefix, "3");
+
+        assert_eq!(prefix.command(Command::Explore), Some((Command::Explore, 1)));
+        assert_eq!(prefix.pending(), None);
+    }
+}
@@ -1,59 +1,119 @@
 use std::marker::Send;
-use crate::game::{GameTick, Command};
+use crate::game::{Command, GameTick, InputContext, MouseInput, MouseAction};
+use crate::game::count_prefix::CountPrefix;
+use crate::game::macro_recorder::MacroRecorder;
 use crate::game::source::Source;
-use crossterm::event::{Event as CEvent, KeyCode, KeyEvent};
+use crate::config::keymap::Keymap;
+use crate::config::macros::Macros;
+use crossterm::event::{Event as CEvent, KeyCode, KeyEvent, MouseButton, MouseEvent};
 use std::{
-    sync::mpsc,
-    thread,
+    error::Error,
+    fmt,
+    sync::{mpsc, Arc},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
 type Result<T> = std::result::Result<T, InputManagerError>;
 
-/// Abstraction that tracks time between the last input event and Crossterm Events
+/// Crossterm Events, or a tick when none came within the tick rate. Inputs also keep when they were read, to measure
+/// how long they take to show up on screen
 #[derive(PartialEq, Debug)]
 enum Event<I> {
-    Input(Duration, I),
-    Tick(Duration),
+    Input(Instant, I),
+    Tick,
 }
 
 type InputEvent = Event<CEvent>;
 
+/// Settings shared with the polling thread, which it checks each time around its loop
+#[derive(Debug)]
+struct PollingState {
+    /// Set to make the polling thread exit
+    shutdown: AtomicBool,
+    /// How long until the polling thread sends an `Event::Tick` (max deltatime), in microseconds
+    tick_rate_micros: AtomicU64,
+}
+
+impl PollingState {
+
+    fn tick_rate(&self) -> Duration {
+        Duration::from_micros(self.tick_rate_micros.load(Ordering::Relaxed))
+    }
+
+}
+
 /// Manages user input by polling on another thread
-/// Cleans up when this struct is dropped
+/// Cleans up when this struct is dropped, stopping the thread and waiting for it to finish
 pub struct InputManager {
     /// Receiver for communicating input on polling thread
     rx: mpsc::Receiver<InputEvent>,
-    /// How long until async thread sends a Event::Tick (max deltatime)
-    tick_rate: Duration,
+    /// Shared with the polling thread
+    polling: Arc<PollingState>,
+    /// The polling thread, until it's joined when dropped
+    poller: Option<JoinHandle<Result<()>>>,
     /// Longest time to wait for any 1 tick from the receiver
-    tick_timeout: Duration
+    tick_timeout: Duration,
+    /// Which command each key gives
+    keymap: Keymap,
+    /// Count typed before the next command, and the last command for repeating
+    count_prefix: CountPrefix,
+    /// Records and plays back macros
+    macros: MacroRecorder,
+    /// When the input `tick` last returned was read, if the tick came from input
+    last_input_read: Option<Instant>,
+    /// When `tick` last returned, which each tick's deltatime is measured from
+    last_tick: Instant,
+    /// Something to tell the player, such as a macro that couldn't be saved
+    message: Option<String>,
 }
 
 impl InputManager {
 
     /// Creates `InputManager` and starts asynchronously polling user input
-    pub fn new(source: impl Source + Send + 'static, tick_rate: Duration, tick_timeout: Duration) -> Self {
+    pub fn new(
+        source: impl Source + Send + 'static,
+        keymap: Keymap,
+        macros: Macros,
+        tick_rate: Duration,
+        tick_timeout: Duration
+    ) -> Self {
         let (sx, rx) = mpsc::channel();
-        let input_manager = InputManager {
-            rx,
-            tick_rate,
-            tick_timeout
-        };
+        let polling = Arc::new(PollingState {
+            shutdown: AtomicBool::new(false),
+            tick_rate_micros: AtomicU64::new(tick_rate.as_micros() as u64),
+        });
 
-        input_manager.start_async_polling(source, sx);
+        let poller = Self::start_async_polling(source, sx, Arc::clone(&polling));
 
-        input_manager
+        InputManager {
+            rx,
+            polling,
+            poller: Some(poller),
+            tick_timeout,
+            keymap,
+            count_prefix: CountPrefix::default(),
+            macros: MacroRecorder::new(macros),
+            last_input_read: None,
+            last_tick: Instant::now(),
+            message: None,
+        }
     }
 
     /// Starts the async thread that polls for input and returns ticks
-    /// Thread exits if any kind of Error is encountered when working with input
-    fn start_async_polling(&self, mut source: impl Source + Send + 'static, sx: mpsc::Sender<InputEvent>) {
-        let tick_rate = self.tick_rate;
+    /// Thread exits when `polling` says to shut down, or if any kind of Error is encountered when working with input
+    fn start_async_polling(
+        mut source: impl Source + Send + 'static,
+        sx: mpsc::Sender<InputEvent>,
+        polling: Arc<PollingState>
+    ) -> JoinHandle<Result<()>> {
         thread::spawn(move || -> Result<()> {
             let mut last_tick = Instant::now();
 
-            loop {
+            while !polling.shutdown.load(Ordering::Relaxed) {
+                let tick_rate = polling.tick_rate();
+
                 // poll for tick rate duration. If no events, send tick event
                 let timeout = tick_rate
                     .checked_sub(last_tick.elapsed())
@@ -61,63 +121,238 @@ impl InputManager {
 
                 // if has event, send it
                 if source.has_event(timeout) {
-                    let time_since_last_tick = Instant::now() - last_tick;
                     let event = source.read()?;
-                    sx.send(Event::Input(time_since_last_tick, event))?;
+                    sx.send(Event::Input(Instant::now(), event))?;
+                    last_tick = Instant::now();
                 }
 
                 // Send a Tick since `tick_rate` has passed
                 if last_tick.elapsed() >= tick_rate {
-                    sx.send(Event::Tick(tick_rate))?;
+                    sx.send(Event::Tick)?;
                     last_tick = Instant::now();
                 }
             }
-        });
+
+            Ok(())
+        })
     }
 
-    /// Blocks until getting an input
-    pub fn tick(&self) -> Result<GameTick> {
-        let rx_result = self.rx.recv_timeout(self.tick_timeout)?;
+    /// Returns the longest time between ticks
+    pub fn tick_rate(&self) -> Duration {
+        self.polling.tick_rate()
+    }
+
+    /// Changes the longest time between ticks. Takes effect from the tick after the next one. Fails for a tick rate
+    /// under a microsecond, as the polling thread would never wait
+    pub fn set_tick_rate(&self, tick_rate: Duration) -> Result<()> {
+        match tick_rate.as_micros() {
+            0 => Err(InputManagerError::InvalidTickRate(tick_rate)),
+            micros => {
+                self.polling.tick_rate_micros.store(micros as u64, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the longest time `tick` waits for input or a tick from the polling thread
+    pub fn tick_timeout(&self) -> Duration {
+        self.tick_timeout
+    }
+
+    /// Changes the longest time `tick` waits for input or a tick from the polling thread
+    pub fn set_tick_timeout(&mut self, tick_timeout: Duration) {
+        self.tick_timeout = tick_timeout;
+    }
+
+    /// Blocks until getting an input. Keys are turned into the commands they are bound to in `context`.
+    ///
+    /// While a macro plays, each tick without input gives its next step, unless the view on top is `busy` with the
+    /// last one
+    ///
+    /// If nothing comes within `tick_timeout`, it gives an idle tick. Each tick's deltatime is the time since the last
+    /// tick returned, so no time is counted twice. Fails if the polling thread has stopped, such as from an error
+    /// reading input
+    pub fn tick(&mut self, context: InputContext, busy: bool) -> Result<GameTick> {
+        let rx_result = match self.rx.recv_timeout(self.tick_timeout) {
+            Ok(event) => event,
+            Err(mpsc::RecvTimeoutError::Timeout) => Event::Tick,
+            Err(error) => return Err(error.into())
+        };
+
+        let now = Instant::now();
+        let deltatime = now - self.last_tick;
+        self.last_tick = now;
+        self.last_input_read = None;
 
         let game_tick = match rx_result {
-            Event::Input(deltatime, event) => Self::match_crossterm_event(deltatime, event),
-            Event::Tick(deltatime) => GameTick::Tick(deltatime)
+            Event::Input(read_at, event) => {
+                self.last_input_read = Some(read_at);
+                self.match_crossterm_event(deltatime, event, context)
+            },
+            Event::Tick if context == InputContext::Game && !busy => match self.macros.next_step() {
+                Some((command, count)) => command_tick(deltatime, command, count),
+                None => GameTick::Tick(deltatime)
+            },
+            Event::Tick => GameTick::Tick(deltatime)
         };
 
         Ok(game_tick)
     }
 
-    fn match_crossterm_event(deltatime: Duration, event: CEvent) -> GameTick {
+    /// Returns when the input behind the last tick was read, or `None` if the last tick didn't come from input
+    pub fn last_input_read(&self) -> Option<Instant> {
+        self.last_input_read
+    }
+
+    /// Returns what's being recorded, to show the player, or `None` if nothing is
+    pub fn macro_status(&self) -> Option<String> {
+        self.macros.status()
+    }
+
+    /// Returns something to tell the player, such as a macro that couldn't be saved, and forgets it
+    pub fn take_message(&mut self) -> Option<String> {
+        self.message.take()
+    }
+
+    /// Stops playing the macro, such as when something unexpected happens
+    pub fn stop_macro(&mut self) {
+        self.macros.stop_playback();
+    }
+
+    /// Returns the tick for `event`. Keys that aren't bound to a command only pass time, except in
+    /// `InputContext::Text`, where they are typed.
+    ///
+    /// In `InputContext::Game`, unbound digits make up a count for the next command, so `5j` moves down 5 times
+    fn match_crossterm_event(&mut self, deltatime: Duration, event: CEvent, context: InputContext) -> GameTick {
         match event {
-            CEvent::Key(key) => {
-                let command = Self::match_key_event(key);
-                GameTick::Command(deltatime, command)
+            CEvent::Key(key) if context == InputContext::Game => self.match_game_key(deltatime, key),
+            CEvent::Key(key) => match (self.keymap.command(context, key), context) {
+                (Some(command), _) => {
+                    self.count_prefix.cancel();
+                    GameTick::Command(deltatime, command)
+                },
+                (None, InputContext::Text) => {
+                    self.count_prefix.cancel();
+                    GameTick::Key(deltatime, key)
+                },
+                (None, _) => GameTick::Tick(deltatime)
+            },
+            CEvent::Mouse(event) => match match_mouse_event(event) {
+                Some(input) => GameTick::Mouse(deltatime, input),
+                None => GameTick::Tick(deltatime)
             },
-            CEvent::Mouse(_) => GameTick::Tick(deltatime), // TODO replace
             CEvent::Resize(_, _) => GameTick::Tick(deltatime) // TODO replace
         }
     }
 
-    // TODO change to have changeable configs
-    fn match_key_event(key: KeyEvent) -> Command {
-        match key.code {
-            KeyCode::Char('k') => Command::Up,
-            KeyCode::Char('j') => Command::Down,
-            KeyCode::Char('h') => Command::Left,
-            KeyCode::Char('l') => Command::Right,
-            KeyCode::Char('q') => Command::Quit,
-            _ => Command::Up
+    /// Returns the tick for `key` pressed in `InputContext::Game`, where keys also make up counts and record and play
+    /// macros. Pressing any key stops the macro that's playing
+    fn match_game_key(&mut self, deltatime: Duration, key: KeyEvent) -> GameTick {
+        self.macros.stop_playback();
+        let command = self.keymap.command(InputContext::Game, key);
+
+        if self.macros.is_choosing_key() {
+            // Keys bound to commands can't play macros, or the command couldn't be used
+            match command {
+                None if key.code != KeyCode::Esc => self.macros.start_recording(key),
+                _ => self.macros.cancel_recording()
+            }
+            return GameTick::Tick(deltatime);
+        }
+
+        match command {
+            Some(Command::RecordMacro) => {
+                self.count_prefix.cancel();
+                if let Err(error) = self.macros.toggle_recording() {
+                    self.message = Some(format!("The macro can't be saved: {}", error));
+                }
+                GameTick::Command(deltatime, Command::RecordMacro)
+            },
+            Some(command) => match self.count_prefix.command(command) {
+                Some((command, count)) => {
+                    self.macros.record(command, count);
+                    command_tick(deltatime, command, count)
+                },
+                None => GameTick::Tick(deltatime)
+            },
+            None if self.macros.play(key) => {
+                self.count_prefix.cancel();
+                GameTick::Tick(deltatime)
+            },
+            // The digits could be numpad keys the player moves with, so they can't be told apart from a count
+            None if self.keymap.binds_digits(InputContext::Game) => {
+                self.count_prefix.cancel();
+                GameTick::Tick(deltatime)
+            },
+            None => {
+                self.count_prefix.push_key(key);
+                GameTick::Tick(deltatime)
+            }
+        }
+    }
+
+}
+
+impl Drop for InputManager {
+
+    fn drop(&mut self) {
+        self.polling.shutdown.store(true, Ordering::Relaxed);
+
+        if let Some(poller) = self.poller.take() {
+            let _ = poller.join();
         }
     }
 
 }
 
+/// Returns the tick for doing `command` `count` times. Only turns are repeated; anything else is done once
+fn command_tick(deltatime: Duration, command: Command, count: u32) -> GameTick {
+    match count {
+        count if count > 1 && command.is_turn() => GameTick::Repeat(deltatime, command, count),
+        _ => GameTick::Command(deltatime, command)
+    }
+}
+
+/// Returns the `MouseInput` for `event`, or `None` for events the game doesn't use, like releasing a button.
+///
+/// Hovering comes from dragging or pressing a button other than the left one. crossterm asks the terminal to report
+/// movement only while a button is held, and its parser throws away the reports terminals send for movement without
+/// one, so turning on any-motion tracking wouldn't get anything through
+fn match_mouse_event(event: MouseEvent) -> Option<MouseInput> {
+    let (action, column, row) = match event {
+        MouseEvent::Down(MouseButton::Left, column, row, _) => (MouseAction::Click, column, row),
+        MouseEvent::Down(_, column, row, _) | MouseEvent::Drag(_, column, row, _) => (MouseAction::Hover, column, row),
+        MouseEvent::ScrollUp(column, row, _) => (MouseAction::ScrollUp, column, row),
+        MouseEvent::ScrollDown(column, row, _) => (MouseAction::ScrollDown, column, row),
+        MouseEvent::Up(..) => return None
+    };
+
+    Some(MouseInput { action, column, row })
+}
+
 /// Error for InputManager related errors
 #[derive(Debug)]
 pub enum InputManagerError {
     CrosstermError(crossterm::ErrorKind),
     RecvTimoutERror(mpsc::RecvTimeoutError),
     SendError,
+    /// Tick rate too short to wait for
+    InvalidTickRate(Duration),
+}
+
+impl Error for InputManagerError {}
+
+impl fmt::Display for InputManagerError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputManagerError::CrosstermError(error) => write!(f, "Could not read input: {}", error),
+            InputManagerError::RecvTimoutERror(error) => write!(f, "Could not receive input: {}", error),
+            InputManagerError::SendError => write!(f, "Input stopped being received"),
+            InputManagerError::InvalidTickRate(tick_rate) => write!(f, "Tick rate of {:?} is too short", tick_rate),
+        }
+    }
+
 }
 
 impl From<crossterm::ErrorKind> for InputManagerError {
@@ -152,6 +387,7 @@ mod test {
     use std::fmt;
     use std::error::Error;
     use crate::game::source::FakeSource;
+    use crossterm::event::KeyModifiers;
 
     /// Error for all timeout events
     #[derive(Debug, Clone)]
@@ -169,7 +405,8 @@ mod test {
 
     /// Convenience method for creating the InputManager
     fn make_input_manager(events: Vec<CEvent>) -> InputManager {
-        InputManager::new(FakeSource::new(events), Duration::from_millis(16), Duration::from_secs(1))
+        let (tick_rate, tick_timeout) = (Duration::from_millis(16), Duration::from_secs(1));
+        InputManager::new(FakeSource::new(events), Keymap::default(), Macros::default(), tick_rate, tick_timeout)
     }
 
     /// Convenience Wrapper of `get_input_manager_events`
@@ -198,11 +435,11 @@ mod test {
     ) -> std::result::Result<Vec<InputEvent>, InputTimeoutError> {
         let mut results = vec!();
         let mut events_so_far = 0;
-        let mut accum_time = Duration::from_millis(0);
+        let start = Instant::now();
 
         while events_so_far < number_events {
-            if accum_time > total_timeout {
-                return Err(InputTimeoutError(accum_time));
+            if start.elapsed() > total_timeout {
+                return Err(InputTimeoutError(start.elapsed()));
             }
 
             let new_event = match rx.recv_timeout(timeout) {
@@ -210,14 +447,8 @@ mod test {
                 Err(_) => return Err(InputTimeoutError(timeout))
             };
 
-            match new_event {
-                Event::Input(delta, _) => {
-                    events_so_far += 1;
-                    accum_time += delta;
-                },
-                Event::Tick(delta) => {
-                    accum_time += delta;
-                }
+            if let Event::Input(..) = new_event {
+                events_so_far += 1;
             }
 
             results.push(new_event);
@@ -228,13 +459,13 @@ mod test {
 
     /// Returns `InputEvent` corresponding to keyboard input character
     fn key_event(letter: char) -> InputEvent {
-        Event::Input(Duration::from_millis(0), crossterm_key(letter))
+        Event::Input(Instant::now(), crossterm_key(letter))
     }
 
-    /// Compares `Event`s ignoring deltatime
+    /// Compares `Event`s ignoring when they were read
     fn compare(former: &InputEvent, latter: &InputEvent) -> bool {
         match (former, latter) {
-            (Event::Tick(_), Event::Tick(_)) => true,
+            (Event::Tick, Event::Tick) => true,
             (Event::Input(_, former_key), Event::Input(_, latter_key)) => former_key == latter_key,
             _ => false
         }
@@ -244,13 +475,14 @@ mod test {
     fn remove_ticks(events: Vec<InputEvent>) -> Vec<InputEvent> {
         events.into_iter().filter_map(|ev: InputEvent| {
             match ev {
-                Event::Tick(_) => None,
+                Event::Tick => None,
                 event => Some(event)
             }
         }).collect::<Vec<InputEvent>>()
     }
 
     /// Returns true if `events` contains `item`
+    #[allow(clippy::ptr_arg, clippy::needless_borrow)]
     fn has_event(events: &Vec<InputEvent>, input_event: &InputEvent) -> bool {
         events.iter().any(|event| compare(&event, input_event))
     }
@@ -276,7 +508,7 @@ mod test {
         let results = get_input_events(&input_manager.rx, 0)?;
 
         let all_ticks = results.into_iter().all(|ev: InputEvent| {
-            ev == Event::Tick(Duration::from_millis(0))
+            ev == Event::Tick
         });
 
         assert!(all_ticks);
@@ -330,4 +562,212 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_unbound_key_is_tick() {
+        let mut input_manager = make_input_manager(vec!());
+        let deltatime = Duration::from_millis(5);
+
+        let bound = input_manager.match_crossterm_event(deltatime, crossterm_key('k'), InputContext::Game);
+        assert_eq!(bound, GameTick::Command(deltatime, crate::game::Command::Up));
+
+        let unbound = input_manager.match_crossterm_event(deltatime, crossterm_key('z'), InputContext::Game);
+        assert_eq!(unbound, GameTick::Tick(deltatime));
+    }
+
+    #[test]
+    fn test_text_keys_are_typed() {
+        let mut input_manager = make_input_manager(vec!());
+        let deltatime = Duration::from_millis(5);
+
+        let typed = input_manager.match_crossterm_event(deltatime, crossterm_key('k'), InputContext::Text);
+        assert!(matches!(typed, GameTick::Key(_, KeyEvent { code: KeyCode::Char('k'), .. })));
+
+        let enter = CEvent::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let confirm = input_manager.match_crossterm_event(deltatime, enter, InputContext::Text);
+        assert_eq!(confirm, GameTick::Command(deltatime, crate::game::Command::Confirm));
+    }
+
+    #[test]
+    fn test_count_prefix() {
+        let mut input_manager = make_input_manager(vec!());
+        let deltatime = Duration::from_millis(5);
+        let mut press = |letter| input_manager.match_crossterm_event(deltatime, crossterm_key(letter), InputContext::Game);
+
+        assert_eq!(press('1'), GameTick::Tick(deltatime));
+        assert_eq!(press('2'), GameTick::Tick(deltatime));
+        assert_eq!(press('j'), GameTick::Repeat(deltatime, crate::game::Command::Down, 12));
+        assert_eq!(press('.'), GameTick::Repeat(deltatime, crate::game::Command::Down, 12));
+        assert_eq!(press('k'), GameTick::Command(deltatime, crate::game::Command::Up));
+        assert_eq!(press('.'), GameTick::Command(deltatime, crate::game::Command::Up));
+
+        // Commands that aren't turns are done once
+        assert_eq!(press('3'), GameTick::Tick(deltatime));
+        assert_eq!(press('o'), GameTick::Command(deltatime, crate::game::Command::Explore));
+    }
+
+    #[test]
+    fn test_numpad_bindings_turn_off_counts() -> TestResult {
+        let keymap = Keymap::parse(&"up = \"numpad8\"\ndown = \"numpad2\"".parse::<toml::Value>()?)?;
+        let (tick_rate, tick_timeout) = (Duration::from_millis(16), Duration::from_secs(1));
+        let mut input_manager = InputManager::new(FakeSource::new(vec!()), keymap, Macros::default(), tick_rate, tick_timeout);
+        let deltatime = Duration::from_millis(5);
+        let mut press = |letter| input_manager.match_crossterm_event(deltatime, crossterm_key(letter), InputContext::Game);
+
+        // A bound digit moves instead of adding to the count
+        assert_eq!(press('8'), GameTick::Command(deltatime, Command::Up));
+        assert_eq!(press('3'), GameTick::Tick(deltatime));
+        assert_eq!(press('2'), GameTick::Command(deltatime, Command::Down));
+        assert_eq!(press('j'), GameTick::Tick(deltatime));
+        assert_eq!(press('.'), GameTick::Command(deltatime, Command::Down));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_macro() {
+        let mut input_manager = make_input_manager(vec!());
+        let deltatime = Duration::from_millis(5);
+        let press = |input_manager: &mut InputManager, code| {
+            let key = CEvent::Key(KeyEvent::new(code, KeyModifiers::NONE));
+            input_manager.match_crossterm_event(deltatime, key, InputContext::Game)
+        };
+
+        // Bound keys can't play macros
+        press(&mut input_manager, KeyCode::Char('m'));
+        assert!(input_manager.macro_status().is_some());
+        assert_eq!(press(&mut input_manager, KeyCode::Char('k')), GameTick::Tick(deltatime));
+        assert_eq!(input_manager.macro_status(), None);
+        assert_eq!(press(&mut input_manager, KeyCode::Char('k')), GameTick::Command(deltatime, Command::Up));
+
+        for &code in &[KeyCode::Char('m'), KeyCode::F(1), KeyCode::Char('l'), KeyCode::Char('2'), KeyCode::Char('j')] {
+            press(&mut input_manager, code);
+        }
+        assert_eq!(input_manager.macro_status().as_deref(), Some("Recording macro for f1 (2 steps)"));
+        press(&mut input_manager, KeyCode::Char('m'));
+        assert_eq!(input_manager.take_message(), None);
+
+        assert_eq!(press(&mut input_manager, KeyCode::F(1)), GameTick::Tick(deltatime));
+
+        // Steps are given in place of ticks, waiting while the game is busy
+        let mut tick = |busy| input_manager.tick(InputContext::Game, busy).ok().map(without_time);
+        assert_eq!(tick(true), Some(GameTick::Tick(Duration::ZERO)));
+        assert_eq!(tick(false), Some(GameTick::Command(Duration::ZERO, Command::Right)));
+        assert_eq!(tick(false), Some(GameTick::Repeat(Duration::ZERO, Command::Down, 2)));
+        assert_eq!(tick(false), Some(GameTick::Tick(Duration::ZERO)));
+    }
+
+    /// Returns `tick` with its deltatime set to zero, as the time between real ticks varies
+    fn without_time(tick: GameTick) -> GameTick {
+        match tick {
+            GameTick::Tick(_) => GameTick::Tick(Duration::ZERO),
+            GameTick::Command(_, command) => GameTick::Command(Duration::ZERO, command),
+            GameTick::Repeat(_, command, count) => GameTick::Repeat(Duration::ZERO, command, count),
+            GameTick::Mouse(_, input) => GameTick::Mouse(Duration::ZERO, input),
+            GameTick::Key(_, key) => GameTick::Key(Duration::ZERO, key),
+        }
+    }
+
+    /// `Source` with no events that sets a flag when it's dropped
+    struct DropFlagSource(Arc<AtomicBool>);
+
+    impl Source for DropFlagSource {
+
+        fn has_event(&self, timeout: Duration) -> bool {
+            thread::sleep(timeout);
+            false
+        }
+
+        fn read(&mut self) -> crossterm::Result<CEvent> {
+            Err(crossterm::ErrorKind::IoError(std::io::Error::other("DropFlagSource has no events")))
+        }
+
+    }
+
+    impl Drop for DropFlagSource {
+
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+
+    }
+
+    #[test]
+    fn test_drop_joins_polling_thread() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let source = DropFlagSource(Arc::clone(&dropped));
+        let input_manager = InputManager::new(
+            source, Keymap::default(), Macros::default(), Duration::from_millis(16), Duration::from_secs(1)
+        );
+
+        drop(input_manager);
+        assert!(dropped.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_timeout_is_idle_tick() {
+        let timeout = Duration::from_millis(10);
+        let mut input_manager = InputManager::new(
+            FakeSource::new(vec!()), Keymap::default(), Macros::default(), Duration::from_secs(5), timeout
+        );
+
+        match input_manager.tick(InputContext::Game, false).ok() {
+            Some(GameTick::Tick(deltatime)) => assert!(deltatime >= timeout && deltatime < Duration::from_secs(5)),
+            tick => panic!("Expected an idle tick, got {:?}", tick)
+        }
+    }
+
+    #[test]
+    fn test_time_isnt_counted_twice() {
+        // Times out before each tick from the polling thread
+        let start = Instant::now();
+        let mut input_manager = InputManager::new(
+            FakeSource::new(vec!()), Keymap::default(), Macros::default(), Duration::from_millis(30), Duration::from_millis(10)
+        );
+
+        let counted: Duration = (0..10)
+            .filter_map(|_| input_manager.tick(InputContext::Game, false).ok())
+            .map(|tick| tick.deltatime())
+            .sum();
+
+        assert!(counted <= start.elapsed());
+    }
+
+    #[test]
+    fn test_change_tick_rate() {
+        let mut input_manager = make_input_manager(vec!());
+        let tick_rate = Duration::from_millis(5);
+        assert!(input_manager.set_tick_rate(tick_rate).is_ok());
+        assert_eq!(input_manager.tick_rate(), tick_rate);
+
+        // The first tick may still wait for the old tick rate
+        input_manager.tick(InputContext::Game, false).ok();
+        let start = Instant::now();
+        let ticks: Vec<_> = (0..3).filter_map(|_| input_manager.tick(InputContext::Game, false).ok()).collect();
+        assert_eq!(ticks.len(), 3);
+        assert!(start.elapsed() < Duration::from_millis(16 * 3));
+
+        assert!(input_manager.set_tick_rate(Duration::ZERO).is_err());
+        assert!(input_manager.set_tick_rate(Duration::from_nanos(500)).is_err());
+        assert_eq!(input_manager.tick_rate(), tick_rate);
+    }
+
+    #[test]
+    fn test_mouse_events() {
+        let modifiers = crossterm::event::KeyModifiers::NONE;
+
+        let click = match_mouse_event(MouseEvent::Down(MouseButton::Left, 3, 4, modifiers));
+        assert_eq!(click, Some(MouseInput { action: MouseAction::Click, column: 3, row: 4 }));
+
+        let drag = match_mouse_event(MouseEvent::Drag(MouseButton::Left, 5, 6, modifiers));
+        assert_eq!(drag, Some(MouseInput { action: MouseAction::Hover, column: 5, row: 6 }));
+
+        let right_click = match_mouse_event(MouseEvent::Down(MouseButton::Right, 7, 8, modifiers));
+        assert_eq!(right_click, Some(MouseInput { action: MouseAction::Hover, column: 7, row: 8 }));
+
+        let scroll = match_mouse_event(MouseEvent::ScrollDown(0, 0, modifiers));
+        assert_eq!(scroll.map(|input| input.action), Some(MouseAction::ScrollDown));
+
+        assert_eq!(match_mouse_event(MouseEvent::Up(MouseButton::Left, 3, 4, modifiers)), None);
+    }
+
 }
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+use crossterm::event::KeyEvent;
+use super::Command;
+use crate::config::ConfigError;
+use crate::config::keymap::KeyBinding;
+use crate::config::macros::{MacroStep, Macros};
+
+/// What `MacroRecorder` is doing with the commands it's given
+#[derive(Debug)]
+enum RecordingState {
+    Off,
+    /// Waiting for the key the macro will be played with
+    ChoosingKey,
+    /// Recording commands into the macro `key` will play
+    Recording { key: KeyEvent, steps: Vec<MacroStep> },
+}
+
+/// Records the commands the player gives into macros bound to keys, and plays macros back one step at a time
+#[derive(Debug)]
+pub struct MacroRecorder {
+    macros: Macros,
+    state: RecordingState,
+    /// Steps of the playing macro that haven't been given yet
+    playback: VecDeque<MacroStep>,
+}
+
+impl MacroRecorder {
+
+    /// Creates `MacroRecorder` that plays and adds to `macros`
+    pub fn new(macros: Macros) -> Self {
+        MacroRecorder { macros, state: RecordingState::Off, playback: VecDeque::new() }
+    }
+
+    /// Starts choosing the key for a new macro, or if one is being recorded, finishes it and saves it. A macro that
+    /// can't be saved can still be played until the game closes
+    pub fn toggle_recording(&mut self) -> Result<(), ConfigError> {
+        match std::mem::replace(&mut self.state, RecordingState::Off) {
+            RecordingState::Off => self.state = RecordingState::ChoosingKey,
+            RecordingState::ChoosingKey => (),
+            RecordingState::Recording { key, steps } => {
+                self.macros.insert(key, steps);
+                self.macros.save()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns what's being recorded, to show the player, or `None` if nothing is
+    pub fn status(&self) -> Option<String> {
+        match &self.state {
+            RecordingState::Off => None,
+            RecordingState::ChoosingKey => Some("Press a key to record a macro for, or esc to cancel".to_string()),
+            RecordingState::Recording { key, steps } => {
+                Some(format!("Recording macro for {} ({} steps)", KeyBinding::from(*key), steps.len()))
+            }
+        }
+    }
+
+    /// Returns `true` if the next key pressed will be the key of a new macro
+    pub fn is_choosing_key(&self) -> bool {
+        matches!(self.state, RecordingState::ChoosingKey)
+    }
+
+    /// Starts recording the macro `key` plays, after `toggle_recording` was used to choose a key
+    pub fn start_recording(&mut self, key: KeyEvent) {
+        if self.is_choosing_key() {
+            self.state = RecordingState::Recording { key, steps: vec!() };
+        }
+    }
+
+    /// Stops recording without changing any macro
+    pub fn cancel_recording(&mut self) {
+        self.state = RecordingState::Off;
+    }
+
+    /// Adds doing `command` `count` times to the macro being recorded, if there is one
+    pub fn record(&mut self, command: Command, count: u32) {
+        if let RecordingState::Recording { steps, .. } = &mut self.state {
+            steps.push((command, count));
+        }
+    }
+
+    /// Starts playing the macro `key` is bound to. Returns `false` if it isn't bound to one
+    pub fn play(&mut self, key: KeyEvent) -> bool {
+        match self.macros.get(key) {
+            Some(steps) => {
+                self.playback = steps.iter().copied().collect();
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Returns the next step of the playing macro, or `None` if there isn't one
+    pub fn next_step(&mut self) -> Option<MacroStep> {
+        self.playback.pop_front()
+    }
+
+    /// Stops playing the macro, skipping the rest of its steps
+    pub fn stop_playback(&mut self) {
+        self.playback.clear();
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_record_and_play() {
+        let mut recorder = MacroRecorder::new(Macros::default());
+        let f1 = KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE);
+
+        // Commands aren't recorded until a key is chosen
+        assert!(recorder.toggle_recording().is_ok());
+        recorder.record(Command::Up, 1);
+        recorder.start_recording(f1);
+        recorder.record(Command::Right, 1);
+        recorder.record(Command::Down, 3);
+        assert_eq!(recorder.status().as_deref(), Some("Recording macro for f1 (2 steps)"));
+        assert!(recorder.toggle_recording().is_ok());
+        assert_eq!(recorder.status(), None);
+        recorder.record(Command::Left, 1);
+
+        assert!(recorder.play(f1));
+        assert_eq!(recorder.next_step(), Some((Command::Right, 1)));
+
+        recorder.stop_playback();
+        assert_eq!(recorder.next_step(), None);
+        assert!(!recorder.play(KeyEvent::new(KeyCode::F(2), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn test_save_error() {
+        // Nothing can be saved inside a file
+        let path = std::env::current_exe().unwrap_or_default().join("macros.toml");
+        let mut recorder = MacroRecorder::new(Macros::load_from(path).unwrap_or_default());
+        let f1 = KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE);
+
+        assert!(recorder.toggle_recording().is_ok());
+        recorder.start_recording(f1);
+        recorder.record(Command::Up, 1);
+        assert!(recorder.toggle_recording().is_err());
+
+        // Still played until the game closes
+        assert!(recorder.play(f1));
+    }
+}
@@ -1,5 +1,10 @@
 mod command;
 pub mod source;
 pub mod input_manager;
+pub mod animation_gate;
+pub mod count_prefix;
+pub mod recording;
+pub mod macro_recorder;
+pub mod save;
 
-pub use command::{GameTick, Command};
+pub use command::{GameTick, Command, InputContext, MouseInput, MouseAction};
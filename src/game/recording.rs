@@ -0,0 +1,347 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent};
+use crossterm::ErrorKind as CrosstermErrorKind;
+use super::source::{EventSource, Source};
+
+type Result<T> = std::result::Result<T, CrosstermErrorKind>;
+
+/// First line of every recording, so other files aren't mistaken for one
+const HEADER: &str = "# terminal-dungeon input recording";
+
+/// `Source` that passes on the events of another `Source`, writing each one to a file along with when it happened,
+/// so it can be played back with `ReplaySource`
+///
+/// Each line of the file is the milliseconds since recording started, followed by the event, such as `1520 key 0 char
+/// 107` for pressing `k` 1.52 seconds in
+pub struct RecordingSource<S: Source = EventSource> {
+    source: S,
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl<S: Source> RecordingSource<S> {
+
+    /// Creates `RecordingSource` that records the events of `source` to a new file at `path`
+    pub fn create(path: &Path, source: S) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", HEADER)?;
+
+        Ok(RecordingSource { source, file, started: Instant::now() })
+    }
+
+}
+
+impl<S: Source> Source for RecordingSource<S> {
+
+    fn has_event(&self, timeout: Duration) -> bool {
+        self.source.has_event(timeout)
+    }
+
+    fn read(&mut self) -> Result<Event> {
+        let event = self.source.read()?;
+
+        // Flushed every event so the recording survives the game crashing, which is often why it's wanted
+        writeln!(self.file, "{} {}", self.started.elapsed().as_millis(), encode_event(&event))?;
+        self.file.flush()?;
+
+        Ok(event)
+    }
+
+}
+
+/// How fast `ReplaySource` plays back events
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ReplayTiming {
+    /// With the same time between events as when they were recorded
+    Original,
+    /// Each event as soon as it's asked for
+    Fast,
+}
+
+/// `Source` that plays back events recorded by `RecordingSource`, then hands over to another `Source` once every
+/// recorded event has been played. Events from the other `Source` while the recording plays are thrown away, so keys
+/// pressed during a replay don't all arrive once it ends
+///
+/// With the original timing, `has_event` waits for the next event the way a real source's poll does, blocking for
+/// up to its timeout, so the gaps between events are played back on the input thread rather than by the main loop
+pub struct ReplaySource<S: Source = EventSource> {
+    /// Events left to play, along with when to play them since the replay started
+    events: VecDeque<(Duration, Event)>,
+    timing: ReplayTiming,
+    started: Instant,
+    /// Where events come from after the recording runs out
+    then: S,
+}
+
+impl<S: Source> ReplaySource<S> {
+
+    /// Creates `ReplaySource` that plays the recording at `path`, then reads from `then`
+    pub fn open(path: &Path, timing: ReplayTiming, then: S) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?), timing, then)
+    }
+
+    /// Creates `ReplaySource` that plays the recording read from `reader`, then reads from `then`
+    pub fn from_reader(reader: impl BufRead, timing: ReplayTiming, then: S) -> io::Result<Self> {
+        let mut lines = reader.lines();
+
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an input recording"));
+        }
+
+        let mut events = VecDeque::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event = decode_line(&line).ok_or_else(|| {
+                // Counting from 1, after the header
+                io::Error::new(io::ErrorKind::InvalidData, format!("Line {} of the recording is invalid", i + 2))
+            })?;
+            events.push_back(event);
+        }
+
+        Ok(ReplaySource { events, timing, started: Instant::now(), then })
+    }
+
+}
+
+impl<S: Source> Source for ReplaySource<S> {
+
+    fn has_event(&self, timeout: Duration) -> bool {
+        let due = match (self.events.front(), self.timing) {
+            (None, _) => return self.then.has_event(timeout),
+            (Some(_), ReplayTiming::Fast) => return true,
+            (Some((due, _)), ReplayTiming::Original) => *due
+        };
+
+        // Waits like a real source would, so the game sees the same gaps between events
+        let wait = due.saturating_sub(self.started.elapsed());
+        if wait <= timeout {
+            thread::sleep(wait);
+            true
+        } else {
+            thread::sleep(timeout);
+            false
+        }
+    }
+
+    fn read(&mut self) -> Result<Event> {
+        match self.events.pop_front() {
+            Some((_, event)) => {
+                while self.then.has_event(Duration::from_millis(0)) {
+                    self.then.read()?;
+                }
+                Ok(event)
+            },
+            None => self.then.read()
+        }
+    }
+
+}
+
+/// Returns `event` written as text, as it's stored in recordings
+fn encode_event(event: &Event) -> String {
+    match event {
+        Event::Key(key) => format!("key {} {}", key.modifiers.bits(), encode_key_code(key.code)),
+        Event::Mouse(mouse) => {
+            let (kind, button, column, row, modifiers) = match *mouse {
+                MouseEvent::Down(button, column, row, modifiers) => ("down", Some(button), column, row, modifiers),
+                MouseEvent::Up(button, column, row, modifiers) => ("up", Some(button), column, row, modifiers),
+                MouseEvent::Drag(button, column, row, modifiers) => ("drag", Some(button), column, row, modifiers),
+                MouseEvent::ScrollDown(column, row, modifiers) => ("scrolldown", None, column, row, modifiers),
+                MouseEvent::ScrollUp(column, row, modifiers) => ("scrollup", None, column, row, modifiers),
+            };
+            let button = match button {
+                Some(MouseButton::Left) => "left",
+                Some(MouseButton::Right) => "right",
+                Some(MouseButton::Middle) => "middle",
+                None => "none"
+            };
+
+            format!("mouse {} {} {} {} {}", kind, button, column, row, modifiers.bits())
+        },
+        Event::Resize(width, height) => format!("resize {} {}", width, height)
+    }
+}
+
+fn encode_key_code(code: KeyCode) -> String {
+    let name = match code {
+        KeyCode::Char(character) => return format!("char {}", u32::from(character)),
+        KeyCode::F(number) => return format!("f {}", number),
+        KeyCode::Backspace => "backspace",
+        KeyCode::Enter => "enter",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageup",
+        KeyCode::PageDown => "pagedown",
+        KeyCode::Tab => "tab",
+        KeyCode::BackTab => "backtab",
+        KeyCode::Delete => "delete",
+        KeyCode::Insert => "insert",
+        KeyCode::Null => "null",
+        KeyCode::Esc => "esc",
+    };
+
+    name.to_string()
+}
+
+/// Parses a line of a recording into when the event happened and the event. Returns `None` if it's invalid
+fn decode_line(line: &str) -> Option<(Duration, Event)> {
+    let mut words = line.split_whitespace();
+    let millis: u64 = words.next()?.parse().ok()?;
+
+    let event = match words.next()? {
+        "key" => {
+            let modifiers = KeyModifiers::from_bits(words.next()?.parse().ok()?)?;
+            Event::Key(KeyEvent::new(decode_key_code(&mut words)?, modifiers))
+        },
+        "mouse" => {
+            let kind = words.next()?;
+            let button = match words.next()? {
+                "left" => Some(MouseButton::Left),
+                "right" => Some(MouseButton::Right),
+                "middle" => Some(MouseButton::Middle),
+                "none" => None,
+                _ => return None
+            };
+            let column = words.next()?.parse().ok()?;
+            let row = words.next()?.parse().ok()?;
+            let modifiers = KeyModifiers::from_bits(words.next()?.parse().ok()?)?;
+
+            Event::Mouse(match (kind, button) {
+                ("down", Some(button)) => MouseEvent::Down(button, column, row, modifiers),
+                ("up", Some(button)) => MouseEvent::Up(button, column, row, modifiers),
+                ("drag", Some(button)) => MouseEvent::Drag(button, column, row, modifiers),
+                ("scrolldown", None) => MouseEvent::ScrollDown(column, row, modifiers),
+                ("scrollup", None) => MouseEvent::ScrollUp(column, row, modifiers),
+                _ => return None
+            })
+        },
+        "resize" => Event::Resize(words.next()?.parse().ok()?, words.next()?.parse().ok()?),
+        _ => return None
+    };
+
+    if words.next().is_some() {
+        return None;
+    }
+
+    Some((Duration::from_millis(millis), event))
+}
+
+fn decode_key_code<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<KeyCode> {
+    let code = match words.next()? {
+        "char" => KeyCode::Char(std::char::from_u32(words.next()?.parse().ok()?)?),
+        "f" => KeyCode::F(words.next()?.parse().ok()?),
+        "backspace" => KeyCode::Backspace,
+        "enter" => KeyCode::Enter,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "null" => KeyCode::Null,
+        "esc" => KeyCode::Esc,
+        _ => return None
+    };
+
+    Some(code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::source::FakeSource;
+    use crate::utility::test_util::{crossterm_key, TestResult};
+
+    /// One of each kind of event
+    fn sample_events() -> Vec<Event> {
+        vec!(
+            crossterm_key('k'),
+            crossterm_key(' '),
+            Event::Key(KeyEvent::new(KeyCode::Char('C'), KeyModifiers::CONTROL | KeyModifiers::SHIFT)),
+            Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::ALT)),
+            Event::Mouse(MouseEvent::Down(MouseButton::Left, 3, 4, KeyModifiers::NONE)),
+            Event::Mouse(MouseEvent::ScrollUp(10, 0, KeyModifiers::CONTROL)),
+            Event::Resize(80, 24),
+        )
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for event in sample_events() {
+            let line = format!("250 {}", encode_event(&event));
+            assert_eq!(decode_line(&line), Some((Duration::from_millis(250), event)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(decode_line("key 0 char 107"), None);
+        assert_eq!(decode_line("10 key 0 char"), None);
+        assert_eq!(decode_line("10 key 0 char 107 extra"), None);
+        assert_eq!(decode_line("10 mouse scrollup left 0 0 0"), None);
+        assert_eq!(decode_line("10 teleport"), None);
+
+        let recording = format!("{}\n10 key 0 up\n20 nonsense\n", HEADER);
+        assert!(ReplaySource::from_reader(recording.as_bytes(), ReplayTiming::Fast, FakeSource::new(vec!())).is_err());
+        assert!(ReplaySource::from_reader("10 key 0 up".as_bytes(), ReplayTiming::Fast, FakeSource::new(vec!())).is_err());
+    }
+
+    #[test]
+    fn test_record_then_replay() -> TestResult {
+        let path = std::env::temp_dir().join(format!("terminal-dungeon-recording-{}.txt", std::process::id()));
+
+        let mut recorder = RecordingSource::create(&path, FakeSource::new(sample_events()))?;
+        while recorder.has_event(Duration::from_millis(0)) {
+            recorder.read()?;
+        }
+        drop(recorder);
+
+        let mut replay = ReplaySource::open(&path, ReplayTiming::Fast, FakeSource::new(vec!(crossterm_key('q'))))?;
+        std::fs::remove_file(&path)?;
+
+        let mut replayed = vec!();
+        while replay.has_event(Duration::from_millis(0)) {
+            replayed.push(replay.read()?);
+        }
+
+        // The key pressed while the recording played is thrown away
+        assert_eq!(replayed, sample_events());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_original_timing() -> TestResult {
+        let recording = format!("{}\n0 key 0 up\n60000 key 0 down\n", HEADER);
+        let mut replay = ReplaySource::from_reader(recording.as_bytes(), ReplayTiming::Original, FakeSource::new(vec!()))?;
+
+        assert!(replay.has_event(Duration::from_millis(10)));
+        replay.read()?;
+
+        // The next event isn't due for a minute
+        assert!(!replay.has_event(Duration::from_millis(10)));
+        assert_eq!(replay.events.len(), 1);
+
+        Ok(())
+    }
+}
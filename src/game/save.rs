@@ -0,0 +1,200 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+use crate::config::config_dir;
+
+type Result<T> = std::result::Result<T, SaveError>;
+
+/// What's kept of a game between runs: where the player is, what they're called and which tiles they've seen.
+/// Written when the game is stopped by a signal, and taken back out of the file the next time it starts
+///
+/// ```toml
+/// position = [4, 4]
+/// name = "Rogue"
+/// revealed = ["..##", ".###"]
+/// ```
+///
+/// Each string in `revealed` is a row of the map, with `#` for the tiles that have been seen
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveGame {
+    pub position: (i32, i32),
+    pub name: Option<String>,
+    /// Whether each tile has been seen, a row at a time
+    pub revealed: Vec<Vec<bool>>,
+}
+
+impl SaveGame {
+
+    /// Reads the save at `path` and deletes it, so the same game can't be carried on twice. Returns `None` if there is
+    /// no save there
+    pub fn take_from(path: &Path) -> Result<Option<SaveGame>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let save = SaveGame::parse(&fs::read_to_string(path)?)?;
+        fs::remove_file(path)?;
+
+        Ok(Some(save))
+    }
+
+    /// Parses the contents of a save file
+    pub fn parse(contents: &str) -> Result<SaveGame> {
+        let root: Value = contents.parse()?;
+
+        let position = root.get("position")
+            .and_then(Value::as_array)
+            .and_then(|xy| match xy.as_slice() {
+                [x, y] => Some((x.as_integer()?, y.as_integer()?)),
+                _ => None
+            })
+            .ok_or_else(|| SaveError::InvalidValue("position should be two whole numbers".to_string()))?;
+        let position = (to_coordinate(position.0)?, to_coordinate(position.1)?);
+
+        let name = match root.get("name") {
+            Some(name) => Some(name.as_str()
+                .ok_or_else(|| SaveError::InvalidValue("name should be a string".to_string()))?
+                .to_string()),
+            None => None
+        };
+
+        let revealed = match root.get("revealed") {
+            Some(rows) => rows.as_array()
+                .ok_or_else(|| SaveError::InvalidValue("revealed should be a list".to_string()))?
+                .iter()
+                .map(|row| row.as_str()
+                    .map(|row| row.chars().map(|tile| tile == '#').collect())
+                    .ok_or_else(|| SaveError::InvalidValue("revealed should be a list of strings".to_string())))
+                .collect::<Result<Vec<Vec<bool>>>>()?,
+            None => Vec::new()
+        };
+
+        Ok(SaveGame { position, name, revealed })
+    }
+
+    /// Writes the save to `path`, making its folder if needed
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml())?;
+
+        Ok(())
+    }
+
+    /// Returns the save the way the save file has it
+    fn to_toml(&self) -> String {
+        let mut contents = format!("position = [{}, {}]\n", self.position.0, self.position.1);
+
+        if let Some(name) = &self.name {
+            contents += &format!("name = {}\n", Value::String(name.clone()));
+        }
+
+        let rows: Vec<String> = self.revealed.iter()
+            .map(|row| row.iter().map(|&seen| if seen { '#' } else { '.' }).collect())
+            .map(|row| Value::String(row).to_string())
+            .collect();
+        contents += &format!("revealed = [{}]\n", rows.join(", "));
+
+        contents
+    }
+
+}
+
+/// Returns where the game is saved: `save.toml` in the `terminal-dungeon` folder of the user's config directory
+pub fn save_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("save.toml"))
+}
+
+fn to_coordinate(value: i64) -> Result<i32> {
+    value.try_into().map_err(|_| SaveError::InvalidValue(format!("{} is too far away to be a position", value)))
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    IoError(io::Error),
+    ParseError(toml::de::Error),
+    InvalidValue(String),
+}
+
+impl Error for SaveError {}
+
+impl fmt::Display for SaveError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::IoError(error) => write!(f, "Could not read or write the save file: {}", error),
+            SaveError::ParseError(error) => write!(f, "Could not parse the save file: {}", error),
+            SaveError::InvalidValue(message) => write!(f, "Invalid value in the save file: {}", message),
+        }
+    }
+
+}
+
+impl From<io::Error> for SaveError {
+
+    fn from(error: io::Error) -> Self {
+        SaveError::IoError(error)
+    }
+
+}
+
+impl From<toml::de::Error> for SaveError {
+
+    fn from(error: toml::de::Error) -> Self {
+        SaveError::ParseError(error)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+
+    fn example() -> SaveGame {
+        SaveGame {
+            position: (4, -2),
+            name: Some("Rogue \"the\" brave".to_string()),
+            revealed: vec!(vec!(false, true), vec!(true, true)),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() -> TestResult {
+        let save = example();
+
+        assert_eq!(SaveGame::parse(&save.to_toml())?, save);
+        assert_eq!(SaveGame::parse(&SaveGame::default().to_toml())?, SaveGame::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_saves() {
+        assert!(SaveGame::parse("").is_err());
+        assert!(SaveGame::parse("position = [1]").is_err());
+        assert!(SaveGame::parse("position = [1, 99999999999]").is_err());
+        assert!(SaveGame::parse("position = [1, 2]\nname = 3").is_err());
+        assert!(SaveGame::parse("position = [1, 2]\nrevealed = [true]").is_err());
+        assert!(SaveGame::parse("position = [1, 2").is_err());
+    }
+
+    #[test]
+    fn test_take_removes_the_save() -> TestResult {
+        let path = std::env::temp_dir().join(format!("terminal-dungeon-save-{}.toml", std::process::id()));
+
+        example().save_to(&path)?;
+        assert_eq!(SaveGame::take_from(&path)?, Some(example()));
+        assert!(!path.exists());
+        assert_eq!(SaveGame::take_from(&path)?, None);
+
+        Ok(())
+    }
+
+}
@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::collections::VecDeque;
 use std::time::Duration;
 use crossterm::event::{self, Event};
@@ -18,12 +18,13 @@ pub trait Source {
 }
 
 /// `Source` implementation using Crossterm library functions to read from `stdin`
+#[derive(Default)]
 pub struct EventSource;
 
 impl EventSource {
 
     pub fn new() -> Self {
-        return EventSource;
+        EventSource
     }
 }
 
@@ -59,7 +60,7 @@ impl Source for FakeSource {
     }
 
     fn read(&mut self) -> Result<Event> {
-        let io_error = CrosstermErrorKind::IoError(Error::new(ErrorKind::Other, "Error in FakeSource!"));
+        let io_error = CrosstermErrorKind::IoError(Error::other("Error in FakeSource!"));
         self.events.pop_front().ok_or(io_error)
     }
 
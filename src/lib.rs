@@ -0,0 +1,10 @@
+//! Terminal roguelike drawn with tui and crossterm, with its world run by specs. The game itself is started by
+//! `main.rs`; everything it's built from is here
+
+pub mod config;
+pub mod world;
+pub mod entities;
+pub mod utility;
+pub mod systems;
+pub mod game;
+pub mod views;
@@ -1,51 +1,80 @@
-mod world;
-mod entities;
-mod utility;
-mod systems;
-mod game;
-mod views;
-
-use crossterm::terminal::Clear;
-use crossterm::event::EnableMouseCapture;
-use crossterm::terminal::EnterAlternateScreen;
-use crossterm::terminal::ClearType;
-
-use crate::views::Screen;
-use crate::views::screens::GameScreen;
-use crate::views::ScreenManager;
-
-use crossterm::terminal::disable_raw_mode;
-use crossterm::terminal::enable_raw_mode;
-use crossterm::execute;
+use terminal_dungeon::config::{self, Config};
+use terminal_dungeon::game::recording::{RecordingSource, ReplaySource, ReplayTiming};
+use terminal_dungeon::game::source::EventSource;
+use terminal_dungeon::game::save::{self, SaveGame};
+use terminal_dungeon::views::Screen;
+use terminal_dungeon::views::screens::GameScreen;
+use terminal_dungeon::views::ScreenManager;
+use terminal_dungeon::views::TerminalGuard;
+
 use std::error::Error;
+use std::path::Path;
 
-use std::io::{self, Write};
+/// Where input comes from, picked with command line arguments
+enum Input {
+    Keyboard,
+    Record(RecordingSource),
+    Replay(ReplaySource),
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    enable_raw_mode()?;
-    setup_ui()?;
+    // Load before taking over the terminal, so problems can be printed
+    let config = Config::load().unwrap_or_else(|error| {
+        eprintln!("{}. Using the default config", error);
+        Config::default()
+    });
+    config::init(config);
+
+    let input = parse_args(std::env::args().skip(1))?;
+
+    // Replays are played from the start of a new game, so a saved game is left for later
+    let saved = match (&input, save::save_path()) {
+        (Input::Replay(_), _) | (_, None) => None,
+        (_, Some(path)) => SaveGame::take_from(&path).unwrap_or_else(|error| {
+            eprintln!("{}. Starting a new game", error);
+            None
+        })
+    };
 
-    let mut screen_manager = ScreenManager::new()?;
+    // Dropped last, so the terminal is put back after everything else is done with it
+    let _terminal = TerminalGuard::new()?;
 
-    let game_screen = GameScreen::new();
+    let mut screen_manager = match input {
+        Input::Keyboard => ScreenManager::new()?,
+        Input::Record(source) => ScreenManager::with_source(source)?,
+        Input::Replay(source) => ScreenManager::with_source(source)?,
+    };
+    screen_manager.quit_on_signals()?;
+
+    let mut game_screen = GameScreen::new();
+    if let Some(saved) = saved {
+        game_screen.resume(&saved);
+    }
 
     screen_manager.push_screen(game_screen);
 
     screen_manager.start_main_loop()?;
 
-    disable_raw_mode()?;
-
     Ok(())
 }
 
-/// Sets up stdout for drawing
-fn setup_ui() -> Result<(), Box<dyn Error>> {
-    let mut stdout = io::stdout();
+/// Parses the command line. `--record <file>` saves everything typed to a file, and `--replay <file>` plays it back
+/// before handing over to the keyboard. `--replay-fast <file>` plays it back without waiting between events
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Input, Box<dyn Error>> {
+    let flag = match args.next() {
+        Some(flag) => flag,
+        None => return Ok(Input::Keyboard)
+    };
 
-    enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
+    let path = args.next().ok_or_else(|| format!("{} needs a file", flag))?;
+    let path = Path::new(&path);
 
-    write!(stdout, "{}", Clear(ClearType::All))?;
+    let input = match flag.as_str() {
+        "--record" => Input::Record(RecordingSource::create(path, EventSource::new())?),
+        "--replay" => Input::Replay(ReplaySource::open(path, ReplayTiming::Original, EventSource::new())?),
+        "--replay-fast" => Input::Replay(ReplaySource::open(path, ReplayTiming::Fast, EventSource::new())?),
+        other => return Err(format!("Unknown argument {}", other).into())
+    };
 
-    Ok(())
+    Ok(input)
 }
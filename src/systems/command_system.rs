@@ -1,41 +1,104 @@
-use specs::{Read, System, ReadStorage, WriteStorage, Join};
-use crate::entities::component::{CommandResponse, Position, Collision};
+use specs::{Entities, LazyUpdate, Read, System, ReadStorage, WriteStorage, Join};
+use splines::Interpolation;
+use std::time::Duration;
+use crate::entities::component::{
+    CommandResponse, Position, Collision, PositionAnimation, AnimationProgress, BlocksInput, Targeted
+};
+use crate::entities::effects;
 use crate::game::{Command, GameTick};
 use crate::world::map::Map;
 
 extern crate nalgebra as na;
 use na::Vector2;
 
+/// How far the ring spreads out from something that's hit, in tiles
+const HIT_RING_RADIUS: f32 = 1.5;
+/// How long the ring from a hit takes to spread out
+const HIT_RING_DURATION: Duration = Duration::from_millis(250);
+/// How much longer each tile of a shot's trail lasts than the one behind it
+const SHOT_TRAIL_STEP: Duration = Duration::from_millis(40);
+
 /// System for processing commands
-pub struct CommandSystem;
+pub struct CommandSystem {
+    /// How long moving one tile is animated for. Moves aren't animated if this is 0
+    pub move_animation: Duration
+}
 
 impl <'a> System<'a> for CommandSystem {
     type SystemData = (
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
         Read<'a, GameTick>,
         Read<'a, Map>,
         ReadStorage<'a, CommandResponse>,
         ReadStorage<'a, Collision>,
-        WriteStorage<'a, Position>
+        ReadStorage<'a, Targeted>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, PositionAnimation>,
+        WriteStorage<'a, AnimationProgress>,
+        WriteStorage<'a, BlocksInput>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (game_tick, map, command_response, collision, mut position_storage) = data;
+        let (
+            entities,
+            lazy,
+            game_tick,
+            map,
+            command_response,
+            collision,
+            targeted,
+            mut position_storage,
+            mut position_animations,
+            mut progress,
+            mut blocks_input
+        ) = data;
 
         let mut collidable_entity_locations = vec!();
         for (pos, _) in (&mut position_storage, &collision).join() {
             collidable_entity_locations.push(pos.vec2);
         }
 
-        for (_, position) in (&command_response, &mut position_storage).join() {
+        // Only what can be seen can be shot at
+        let target_locations: Vec<Vector2<i32>> = (&position_storage, &targeted).join()
+            .map(|(pos, _)| pos.vec2)
+            .filter(|pos| map.is_visible(pos[0], pos[1]))
+            .collect();
+
+        for (entity, _, position) in (&entities, &command_response, &mut position_storage).join() {
             match *game_tick {
+                GameTick::Command(_, Command::Fire) => {
+                    for &target in &target_locations {
+                        effects::trail(&entities, &lazy, position.vec2, target, SHOT_TRAIL_STEP);
+                        effects::ring(&entities, &lazy, target, HIT_RING_RADIUS, HIT_RING_DURATION);
+                    }
+                },
                 GameTick::Command(_, command) => {
                     let new_position = get_target_position(position, &command);
 
+                    if new_position == position.vec2 {
+                        continue;
+                    }
+
                     if can_move_onto(new_position, &map, &collidable_entity_locations) {
-                        (*position).vec2 = new_position
+                        // The entity is already at `new_position`, the animation only changes where it's drawn
+                        if self.move_animation > Duration::from_secs(0) {
+                            let animation = PositionAnimation::new(position.vec2, new_position, Interpolation::Cosine);
+                            let _ = position_animations.insert(entity, animation);
+                            let _ = progress.insert(entity, AnimationProgress::new(self.move_animation));
+                            let _ = blocks_input.insert(entity, BlocksInput);
+                        }
+
+                        position.vec2 = new_position
+                    } else if map.is_open(new_position[0], new_position[1]) {
+                        // Hit whatever is standing there
+                        effects::ring(&entities, &lazy, new_position, HIT_RING_RADIUS, HIT_RING_DURATION);
+                    } else {
+                        // Bumped into a wall
+                        effects::sparks(&entities, &lazy, new_position, 6);
                     }
                 },
-                GameTick::Tick(_) => ()
+                GameTick::Tick(_) | GameTick::Repeat(..) | GameTick::Mouse(..) | GameTick::Key(..) => ()
             }
         }
     }
@@ -43,7 +106,7 @@ impl <'a> System<'a> for CommandSystem {
 
 /// Returns coordinate that `command` would move `Position` to
 fn get_target_position(position: &mut Position, command: &Command) -> Vector2<i32> {
-    let mut new_position = (*position).vec2;
+    let mut new_position = position.vec2;
 
     match *command {
         Command::Up => new_position[1] -= 1,
@@ -61,3 +124,83 @@ fn can_move_onto(location: Vector2<i32>, map: &Map, collidable_locations: &Vec<V
     map.is_open(location[0], location[1]) &&
         !collidable_locations.iter().any(|&v| v[0] == location[0] && v[1] == location[1])
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::component::Particle;
+    use crate::world::map;
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    /// World with a player in the top left corner of `map::test_room`, with another entity to their right
+    fn world_with_player() -> World {
+        let mut world = World::new();
+        world.register::<CommandResponse>();
+        world.register::<Collision>();
+        world.register::<Position>();
+        world.register::<PositionAnimation>();
+        world.register::<AnimationProgress>();
+        world.register::<BlocksInput>();
+        world.register::<Particle>();
+        world.register::<Targeted>();
+        world.insert(map::test_room());
+
+        world.create_entity()
+            .with(Position { vec2: Vector2::new(3, 3) })
+            .with(CommandResponse)
+            .with(Collision)
+            .build();
+        world.create_entity()
+            .with(Position { vec2: Vector2::new(4, 3) })
+            .with(Collision)
+            .build();
+
+        world
+    }
+
+    fn particles_after(world: &mut World, command: Command) -> usize {
+        world.insert(GameTick::Command(Duration::from_millis(16), command));
+        CommandSystem { move_animation: Duration::from_secs(0) }.run_now(world);
+        world.maintain();
+        world.read_storage::<Particle>().join().count()
+    }
+
+    #[test]
+    fn test_hits_and_bumps() {
+        let mut world = world_with_player();
+        // Hitting the entity beside the player spreads a ring around it
+        assert!(particles_after(&mut world, Command::Right) > 6);
+
+        let mut world = world_with_player();
+        // Walking into the wall above the player throws sparks
+        assert_eq!(particles_after(&mut world, Command::Up), 6);
+    }
+
+    #[test]
+    fn test_fire_at_target() {
+        let mut world = world_with_player();
+        // Nothing is targeted yet
+        assert_eq!(particles_after(&mut world, Command::Fire), 0);
+
+        let target = world.create_entity()
+            .with(Position { vec2: Vector2::new(6, 3) })
+            .with(Targeted)
+            .build();
+        // The target can't be seen
+        assert_eq!(particles_after(&mut world, Command::Fire), 0);
+
+        world.write_resource::<Map>().reveal(6, 3);
+        let particles = particles_after(&mut world, Command::Fire);
+        // A trail over the 4 tiles from the player to the target, and a ring around it
+        assert!(particles > 4 + 6);
+        let trail = world.read_storage::<Particle>().join()
+            .filter(|particle| particle.velocity == Vector2::new(0., 0.))
+            .count();
+        assert_eq!(trail, 4);
+
+        // Nothing more is spawned once the target is gone
+        world.delete_entity(target).unwrap();
+        world.maintain();
+        assert_eq!(particles_after(&mut world, Command::Fire), particles);
+    }
+}
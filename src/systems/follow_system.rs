@@ -1,5 +1,12 @@
-use specs::{System, Entities, ReadStorage, WriteStorage, Join};
-use crate::entities::component::{Position, Follow};
+use specs::{System, Entities, Read, ReadStorage, WriteStorage, Join};
+use crate::entities::component::{Position, Follow, Camera, PositionAnimation, AnimationProgress};
+use crate::game::GameTick;
+use crate::utility::conversions;
+use crate::utility::text_canvas::TextCanvas;
+use crate::world::map::Map;
+
+extern crate nalgebra as na;
+use na::Vector2;
 
 /// System for controlling camera movement
 pub struct FollowSystem;
@@ -7,20 +14,270 @@ pub struct FollowSystem;
 impl<'a> System<'a> for FollowSystem {
     type SystemData = (
         Entities<'a>,
+        Read<'a, GameTick>,
+        Read<'a, Map>,
+        ReadStorage<'a, TextCanvas>,
         WriteStorage<'a, Position>,
+        WriteStorage<'a, Camera>,
         ReadStorage<'a, Follow>,
+        ReadStorage<'a, PositionAnimation>,
+        ReadStorage<'a, AnimationProgress>,
     );
 
-    fn run(&mut self, (entities, mut position, follow): Self::SystemData) {
-        // Update camera so it follows its target
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, game_tick, map, canvases, mut position, mut camera, follow, movements, progress) = data;
+
+        let deltatime = game_tick.deltatime().as_secs_f32();
+
+        // Update camera so it follows where its target is drawn
         for (entity, follow) in (&*entities, &follow).join() {
-            if let Some(target_position) = position.get(follow.target) {
-                let new_position = target_position.vec2 + follow.offset;
+            let target = match position.get(follow.target) {
+                Some(target_position) => {
+                    target_position.drawn(movements.get(follow.target), progress.get(follow.target)) + follow.offset
+                },
+                None => continue
+            };
 
-                if let Some(camera_position_comp) = position.get_mut(entity) {
-                    camera_position_comp.vec2 = new_position;
-                }
+            if let Some(camera_position_comp) = position.get_mut(entity) {
+                camera_position_comp.vec2 = match camera.get_mut(entity) {
+                    Some(camera) => {
+                        let canvas_dimensions = canvases.get(entity).map_or((0, 0), |canvas| canvas.dimensions());
+                        move_camera(camera, camera_position_comp.vec2, target, deltatime, &map, canvas_dimensions)
+                    },
+                    None => target
+                };
             }
         }
     }
 }
+
+/// Returns where the camera at `current` should move to, to follow `target`, using the options on `camera`.
+/// `canvas_dimensions` is the size of the canvas the camera draws onto
+fn move_camera(
+    camera: &mut Camera,
+    current: Vector2<i32>,
+    target: Vector2<i32>,
+    deltatime: f32,
+    map: &Map,
+    canvas_dimensions: (usize, usize)
+) -> Vector2<i32> {
+    // A camera that hasn't been placed yet goes straight onto its target, rather than starting wherever it was made
+    let mut goal = match camera.focus {
+        Some(_) => apply_dead_zone(current, target, camera.dead_zone),
+        None => target
+    };
+
+    if camera.clamp_to_map {
+        goal = clamp_to_map(goal, map.dimensions(), canvas_dimensions, camera);
+    }
+
+    let goal = goal.map(|n| n as f32);
+    let focus = match (camera.smoothing, camera.focus) {
+        // Exponential smoothing so the camera moves at the same speed regardless of the tick rate
+        (Some(speed), Some(focus)) => focus + (goal - focus) * (1. - (-speed * deltatime).exp()),
+        _ => goal
+    };
+
+    camera.focus = Some(focus);
+    focus.map(|n| n.round() as i32)
+}
+
+/// Returns the closest position to `current` that keeps `target` within `dead_zone` of the camera's center
+fn apply_dead_zone(current: Vector2<i32>, target: Vector2<i32>, dead_zone: Vector2<i32>) -> Vector2<i32> {
+    current.zip_zip_map(&target, &dead_zone, |current, target, dead_zone| {
+        let dead_zone = dead_zone.abs();
+        let distance = target - current;
+
+        if distance > dead_zone {
+            target - dead_zone
+        } else if distance < -dead_zone {
+            target + dead_zone
+        } else {
+            current
+        }
+    })
+}
+
+/// Returns `center` moved so that what `camera` shows on a canvas of `canvas_dimensions` stays inside the map.
+/// Axes where the whole map fits in the view are pinned to the middle of the map
+fn clamp_to_map(
+    center: Vector2<i32>,
+    map_dimensions: (usize, usize),
+    canvas_dimensions: (usize, usize),
+    camera: &Camera
+) -> Vector2<i32> {
+    let clamp_axis = |center: i32, map_length: usize, canvas_length: usize| {
+        let view_length = canvas_length * camera.scale.max(1);
+        if map_length <= view_length {
+            return conversions::as_i32(map_length / 2);
+        }
+
+        // The view covers `center - half_view` up to `view_length` tiles to the right of it
+        let half_view = camera.half_view(canvas_length);
+        let min = half_view;
+        let max = conversions::as_i32(map_length) - conversions::as_i32(view_length) + half_view;
+
+        center.clamp(min, max)
+    };
+
+    Vector2::new(
+        clamp_axis(center[0], map_dimensions.0, canvas_dimensions.0),
+        clamp_axis(center[1], map_dimensions.1, canvas_dimensions.1),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::systems::text_render_system;
+    use crate::world::map;
+    use specs::{Builder, RunNow, World, WorldExt};
+    use splines::Interpolation;
+    use std::time::Duration;
+
+    #[test]
+    fn test_dead_zone() {
+        let dead_zone = Vector2::new(2, 1);
+        let current = Vector2::new(10, 10);
+
+        assert_eq!(apply_dead_zone(current, Vector2::new(12, 9), dead_zone), current);
+        assert_eq!(apply_dead_zone(current, Vector2::new(13, 10), dead_zone), Vector2::new(11, 10));
+        assert_eq!(apply_dead_zone(current, Vector2::new(5, 14), dead_zone), Vector2::new(7, 13));
+    }
+
+    #[test]
+    fn test_no_dead_zone_centers() {
+        let target = Vector2::new(3, -4);
+        assert_eq!(apply_dead_zone(Vector2::new(10, 10), target, Vector2::new(0, 0)), target);
+    }
+
+    #[test]
+    fn test_clamp_to_map() {
+        let map = (40, 40);
+        let view = (10, 6);
+        let camera = Camera::default();
+
+        assert_eq!(clamp_to_map(Vector2::new(0, 0), map, view, &camera), Vector2::new(5, 3));
+        assert_eq!(clamp_to_map(Vector2::new(20, 20), map, view, &camera), Vector2::new(20, 20));
+        assert_eq!(clamp_to_map(Vector2::new(50, 50), map, view, &camera), Vector2::new(35, 37));
+    }
+
+    #[test]
+    fn test_clamp_small_map() {
+        let center = Vector2::new(-3, 2);
+        assert_eq!(clamp_to_map(center, (5, 20), (10, 10), &Camera::default()), Vector2::new(2, 5));
+    }
+
+    #[test]
+    fn test_clamp_scaled_odd_canvas() {
+        // Drawn 5 cells across at 2 tiles each, the view reaches 4 tiles left of its center and 6 to the right
+        let camera = Camera { scale: 2, ..Camera::default() };
+        let canvas = (5, 5);
+
+        assert_eq!(clamp_to_map(Vector2::new(0, 0), (40, 40), canvas, &camera), Vector2::new(4, 4));
+        assert_eq!(clamp_to_map(Vector2::new(40, 40), (40, 40), canvas, &camera), Vector2::new(34, 34));
+    }
+
+    #[test]
+    fn test_new_camera_shows_map_smaller_than_view() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Follow>();
+        world.register::<Camera>();
+        world.register::<TextCanvas>();
+        world.register::<PositionAnimation>();
+        world.register::<AnimationProgress>();
+        world.insert(GameTick::default());
+        world.insert(map::test_room());
+
+        let target = world.create_entity()
+            .with(Position { vec2: Vector2::new(4, 4) })
+            .build();
+        let camera = Camera { dead_zone: Vector2::new(4, 2), clamp_to_map: true, ..Camera::default() };
+        let camera = world.create_entity()
+            .with(Position { vec2: Vector2::new(0, 0) })
+            .with(Follow { target, offset: Vector2::new(0, 0) })
+            .with(camera)
+            .with(TextCanvas::with_size(30, 30))
+            .build();
+
+        FollowSystem.run_now(&world);
+
+        // The 10 x 10 room is pinned in the middle of the view, rather than left where the camera was made
+        let positions = world.read_storage::<Position>();
+        assert_eq!(positions.get(camera).map(|position| position.vec2), Some(Vector2::new(5, 5)));
+
+        let cameras = world.read_storage::<Camera>();
+        let canvases = world.read_storage::<TextCanvas>();
+        let (camera, canvas) = (cameras.get(camera).unwrap(), canvases.get(camera).unwrap());
+        let center = Position { vec2: Vector2::new(5, 5) };
+        assert_eq!(text_render_system::canvas_to_world((10, 10), camera, &center, canvas), (0, 0));
+    }
+
+    #[test]
+    fn test_new_camera_starts_on_target() {
+        let mut camera = Camera { dead_zone: Vector2::new(4, 2), ..Camera::default() };
+        let target = Vector2::new(4, 4);
+
+        assert_eq!(move_camera(&mut camera, Vector2::new(0, 0), target, 0.016, &Map::default(), (0, 0)), target);
+        // Once placed, the dead zone applies
+        assert_eq!(move_camera(&mut camera, target, Vector2::new(7, 4), 0.016, &Map::default(), (0, 0)), target);
+    }
+
+    #[test]
+    fn test_smoothing_approaches_target() {
+        let mut camera = Camera { smoothing: Some(10.), focus: Some(Vector2::new(0., 0.)), ..Camera::default() };
+        let map = Map::default();
+        let mut position = Vector2::new(0, 0);
+        let target = Vector2::new(20, 0);
+
+        position = move_camera(&mut camera, position, target, 0.016, &map, (0, 0));
+        assert!(position[0] > 0 && position[0] < 20);
+
+        for _ in 0..100 {
+            position = move_camera(&mut camera, position, target, 0.016, &map, (0, 0));
+        }
+        assert_eq!(position, target);
+    }
+
+    #[test]
+    fn test_unplaced_camera_snaps() {
+        let mut camera = Camera { smoothing: Some(10.), ..Camera::default() };
+        let target = Vector2::new(20, 5);
+
+        assert_eq!(move_camera(&mut camera, Vector2::new(0, 0), target, 0.016, &Map::default(), (0, 0)), target);
+        assert_eq!(camera.focus, Some(Vector2::new(20., 5.)));
+    }
+
+    #[test]
+    fn test_follows_drawn_position() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Follow>();
+        world.register::<Camera>();
+        world.register::<TextCanvas>();
+        world.register::<PositionAnimation>();
+        world.register::<AnimationProgress>();
+        world.insert(GameTick::default());
+        world.insert(Map::default());
+
+        // A quarter of the way through moving from (0, 0) to (4, 0)
+        let mut progress = AnimationProgress::new(Duration::from_millis(100));
+        progress.current = Duration::from_millis(25);
+        let target = world.create_entity()
+            .with(Position { vec2: Vector2::new(4, 0) })
+            .with(PositionAnimation::new(Vector2::new(0, 0), Vector2::new(4, 0), Interpolation::Linear))
+            .with(progress)
+            .build();
+        let follower = world.create_entity()
+            .with(Position { vec2: Vector2::new(0, 0) })
+            .with(Follow { target, offset: Vector2::new(0, 0) })
+            .build();
+
+        FollowSystem.run_now(&world);
+
+        let positions = world.read_storage::<Position>();
+        assert_eq!(positions.get(follower).map(|position| position.vec2), Some(Vector2::new(1, 0)));
+        assert_eq!(positions.get(target).map(|position| position.vec2), Some(Vector2::new(4, 0)));
+    }
+}
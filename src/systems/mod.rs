@@ -1,7 +1,17 @@
 mod follow_system;
 mod text_render_system;
 mod command_system;
+mod visibility_system;
+mod modifier_animation_system;
+mod particle_system;
+mod position_animation_system;
+mod timed_system;
 
 pub use follow_system::FollowSystem;
-pub use text_render_system::TextRenderSystem;
+pub use text_render_system::{TextRenderSystem, canvas_to_world};
 pub use command_system::CommandSystem;
+pub use visibility_system::VisibilitySystem;
+pub use modifier_animation_system::ModifierAnimationSystem;
+pub use particle_system::ParticleSystem;
+pub use position_animation_system::PositionAnimationSystem;
+pub use timed_system::{Timed, SystemTimes};
@@ -0,0 +1,28 @@
+use std::time::Duration;
+use specs::{System, Read, WriteStorage, Join};
+use crate::entities::component::ModifierAnimation;
+use crate::game::GameTick;
+
+/// System that advances every `ModifierAnimation` by the tick's deltatime
+pub struct ModifierAnimationSystem;
+
+impl<'a> System<'a> for ModifierAnimationSystem {
+    type SystemData = (
+        Read<'a, GameTick>,
+        WriteStorage<'a, ModifierAnimation>,
+    );
+
+    fn run(&mut self, (game_tick, mut animations): Self::SystemData) {
+        let deltatime = game_tick.deltatime();
+
+        for animation in (&mut animations).join() {
+            animation.elapsed += deltatime;
+
+            // Keep within one period so `elapsed` doesn't grow forever
+            if animation.period.as_nanos() > 0 {
+                let nanos = animation.elapsed.as_nanos() % animation.period.as_nanos();
+                animation.elapsed = Duration::from_nanos(nanos as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use specs::{System, Entities, Read, WriteStorage, Join};
+use crate::entities::component::{Particle, Position};
+use crate::game::GameTick;
+
+/// System that moves particles along, and deletes them once their life is over
+pub struct ParticleSystem;
+
+impl<'a> System<'a> for ParticleSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, GameTick>,
+        WriteStorage<'a, Particle>,
+        WriteStorage<'a, Position>,
+    );
+
+    fn run(&mut self, (entities, game_tick, mut particles, mut positions): Self::SystemData) {
+        let deltatime = game_tick.deltatime();
+
+        for (entity, particle, position) in (&entities, &mut particles, &mut positions).join() {
+            particle.progress.current += deltatime;
+
+            if particle.progress.is_finished() {
+                let _ = entities.delete(entity);
+            } else {
+                position.vec2 = particle.current_position();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::effects;
+    use specs::{World, WorldExt, RunNow, LazyUpdate};
+    use std::time::Duration;
+
+    extern crate nalgebra as na;
+    use na::Vector2;
+
+    #[test]
+    fn test_particles_expire() {
+        let mut world = World::new();
+        world.register::<Particle>();
+        world.register::<Position>();
+        world.insert(GameTick::Tick(Duration::from_millis(100)));
+
+        effects::sparks(&world.entities(), &world.read_resource::<LazyUpdate>(), Vector2::new(5, 5), 4);
+        world.maintain();
+        assert_eq!(world.read_storage::<Particle>().join().count(), 4);
+
+        // Longest spark lives 450ms
+        for _ in 0..5 {
+            ParticleSystem.run_now(&world);
+            world.maintain();
+        }
+        assert_eq!(world.read_storage::<Particle>().join().count(), 0);
+    }
+}
@@ -0,0 +1,35 @@
+use specs::{System, Entities, Read, WriteStorage, Join};
+use crate::entities::component::{AnimationProgress, BlocksInput, PositionAnimation};
+use crate::game::GameTick;
+
+/// System that plays `PositionAnimation`s, and removes them once they finish
+pub struct PositionAnimationSystem;
+
+impl<'a> System<'a> for PositionAnimationSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, GameTick>,
+        WriteStorage<'a, PositionAnimation>,
+        WriteStorage<'a, AnimationProgress>,
+        WriteStorage<'a, BlocksInput>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, game_tick, mut animations, mut progress, mut blocks_input) = data;
+
+        let mut finished = vec!();
+        for (entity, _, progress) in (&entities, &animations, &mut progress).join() {
+            progress.current += game_tick.deltatime();
+
+            if progress.is_finished() {
+                finished.push(entity);
+            }
+        }
+
+        for entity in finished {
+            animations.remove(entity);
+            progress.remove(entity);
+            blocks_input.remove(entity);
+        }
+    }
+}
@@ -1,129 +1,242 @@
-use tui::style::Color;
-use specs::{System, ReadStorage, Read, Write, Join};
+use tui::style::{Color, Modifier};
+use specs::{System, ReadStorage, WriteStorage, Read, Join};
 
-use crate::entities::component::{Position, Appearance, Camera};
+use crate::entities::component::{
+    Position,
+    Appearance,
+    Camera,
+    ModifierAnimation,
+    Targeted,
+    Particle,
+    PositionAnimation,
+    AnimationProgress
+};
 use crate::utility::text_canvas::{TextCanvas, CanvasSymbol};
 use crate::utility::conversions;
+use crate::utility::glyph::TileFont;
 use crate::world::map::{Map, Tile};
+use crate::config::theme::{ColorRole, Theme};
 
 extern crate nalgebra as na;
 use na::Vector2;
 
-/// System that renders the area near a camera onto a TextCanvas
+/// System that renders the area near each camera onto that camera's TextCanvas
 pub struct TextRenderSystem;
 
 impl<'a> System<'a> for TextRenderSystem {
     type SystemData = (
         Read<'a, Map>,
-        Write<'a, TextCanvas>,
+        Read<'a, Theme>,
+        Read<'a, TileFont>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Appearance>,
-        ReadStorage<'a, Camera>
+        ReadStorage<'a, ModifierAnimation>,
+        ReadStorage<'a, Targeted>,
+        ReadStorage<'a, Particle>,
+        ReadStorage<'a, PositionAnimation>,
+        ReadStorage<'a, AnimationProgress>,
+        ReadStorage<'a, Camera>,
+        WriteStorage<'a, TextCanvas>
     );
 
 
     fn run(&mut self, data: Self::SystemData) {
-        let (map, mut canvas, pos, app, cam) = data;
+        let (
+            map,
+            theme,
+            tile_font,
+            pos,
+            app,
+            animations,
+            targeted,
+            particles,
+            movements,
+            progress,
+            cam,
+            mut canvases
+        ) = data;
 
-        let camera_position = match get_camera_position(&cam, &pos) {
-            Some(camera_position) => camera_position,
-            None => return // Can't render without a camera...
-        };
+        let drawn_entities = (&pos, &app, movements.maybe(), progress.maybe(), animations.maybe(), targeted.maybe())
+            .join()
+            .map(|(position, appearence, movement, progress, animation, targeted)| {
+                (position.drawn(movement, progress), appearence, animation, targeted)
+            });
+        let entity_symbols = get_entity_symbols(&map, &theme, drawn_entities);
+        let visible_particles: Vec<&Particle> = particles.join()
+            .filter(|particle| {
+                let position = particle.current_position();
+                map.is_visible(position[0], position[1])
+            })
+            .collect();
+
+        for (camera, camera_position, canvas) in (&cam, &pos, &mut canvases).join() {
+            draw_map(camera, camera_position, canvas, &map, &theme, *tile_font);
 
-        draw_map(camera_position, &mut canvas, &map);
+            draw_entities(&entity_symbols, camera, camera_position, canvas);
 
-        draw_entities(&pos, &app, camera_position, &mut canvas);
+            draw_particles(&visible_particles, camera, camera_position, canvas);
+        }
     }
 
 }
 
-/// Returns reference to the `Position` of a camera in the world
-/// The Camera chosen is essentially random
-fn get_camera_position<'a>(
-    cam: &'a ReadStorage<Camera>,
-    pos: &'a ReadStorage<Position>
-) -> Option<&'a Position> {
-    // Use the camera
-    let mut camera_position: Option<&Position> = None;
-    for (_, position) in (&*cam, &*pos).join() {
-        camera_position = Some(position);
-        break;
+fn draw_map(
+    camera: &Camera,
+    camera_position: &Position,
+    canvas: &mut TextCanvas,
+    map: &Map,
+    theme: &Theme,
+    tile_font: TileFont
+) {
+    let (width, height) = canvas.dimensions();
+
+    for i in 0..width {
+        for j in 0..height {
+            let (x, y) = canvas_to_world((i, j), camera, camera_position, canvas);
+            let symbol = get_symbol_for_location(x, y, camera.scale, map, theme, tile_font);
+
+            canvas.set_symbol(Vector2::new(i, j), symbol);
+        }
     }
-    camera_position
 }
 
-fn draw_map(camera_position: &Position, canvas: &mut TextCanvas, map: &Map) {
-    let (start_x, end_x, start_y, end_y) = get_camera_corners(&camera_position, canvas.dimensions());
 
-    for i in start_x..end_x {
-        for j in start_y..end_y {
-            let symbol = get_symbol_for_location(i, j, map);
+/// Gets the `CanvasSymbol` for the `scale` x `scale` block of tiles with its top-left at `(x, y)` in world space.
+/// Walls take priority so thin walls are still visible when zoomed out.
+/// Tiles that have never been seen are blank, and tiles that were seen before but can't be seen now are dim.
+/// With `TileFont::Box` walls join up with their neighbours, except when zoomed out where the joins would be wrong
+fn get_symbol_for_location(x: i32, y: i32, scale: usize, map: &Map, theme: &Theme, tile_font: TileFont) -> CanvasSymbol {
+    let mut in_bounds = false;
+    let mut revealed_tile = None;
+    let mut visible = false;
+
+    for (i, j, &tile) in map.rect(x, y, scale, scale) {
+        let (i, j) = (conversions::as_i32(i), conversions::as_i32(j));
+        in_bounds = true;
 
-            match world_to_canvas((i, j), camera_position, &canvas) {
-                Some((x, y)) => canvas.set_symbol(Vector2::new(x, y), symbol),
-                None => ()
-            };
+        if !map.is_revealed(i, j) {
+            continue;
         }
-    }
-}
 
+        visible |= map.is_visible(i, j);
+        if !matches!(revealed_tile, Some((_, Tile::Wall))) {
+            revealed_tile = Some(((i, j), tile));
+        }
+    }
 
-/// Gets the `CanvasSymbol` for the `(x, y)` pair on the screen. `(x, y)` are
-/// the coordinates in world space
-fn get_symbol_for_location(x: i32, y: i32, map: &Map) -> CanvasSymbol {
-    if map.in_bounds(x, y) {
-        get_canvas_symbol_for_tile(&map[x as usize][y as usize])
-    } else {
-        CanvasSymbol {
+    if !in_bounds {
+        return CanvasSymbol {
             character: '?',
             foreground: Color::Rgb(30, 30, 30),
             background: Color::Rgb(0, 0, 0),
             modifiers: vec!()
-        }
+        };
+    }
+
+    match revealed_tile {
+        Some(((i, j), tile)) => {
+            let mut symbol = get_canvas_symbol_for_tile(&tile, theme, tile_font);
+            if tile == Tile::Wall && tile_font == TileFont::Box && scale == 1 {
+                if let Some(&glyph) = map.wall_glyph(i, j) {
+                    symbol.character = glyph;
+                }
+            }
+            if !visible {
+                symbol.modifiers.push(Modifier::DIM);
+            }
+            symbol
+        },
+        None => CanvasSymbol::default()
     }
 }
 
+/// Returns the world position and `CanvasSymbol` of every entity that can currently be seen, given the position each
+/// entity is drawn at
+fn get_entity_symbols<'e>(
+    map: &Map,
+    theme: &Theme,
+    entities: impl Iterator<Item = (Vector2<i32>, &'e Appearance, Option<&'e ModifierAnimation>, Option<&'e Targeted>)>
+) -> Vec<((i32, i32), CanvasSymbol)> {
+    entities
+        .filter(|(position, _, _, _)| map.is_visible(position[0], position[1]))
+        .map(|(position, appearence, animation, targeted)| {
+            let mut symbol = get_canvas_symbol_for_appearence(appearence, theme);
+
+            if let Some(animation) = animation {
+                if animation.is_on() {
+                    symbol.modifiers.push(animation.modifier);
+                }
+            }
+
+            if targeted.is_some() {
+                symbol.modifiers.push(Modifier::REVERSED);
+            }
+
+            ((position[0], position[1]), symbol)
+        })
+        .collect()
+}
+
 fn draw_entities(
-    pos: &ReadStorage<Position>,
-    app: &ReadStorage<Appearance>,
+    entity_symbols: &[((i32, i32), CanvasSymbol)],
+    camera: &Camera,
     camera_position: &Position,
     canvas: &mut TextCanvas
 ) {
-        for (position, appearence) in (&*pos, &*app).join() {
-            let position = (position.vec2[0], position.vec2[1]);
+    for (position, symbol) in entity_symbols {
+        if let Some((x, y)) = world_to_canvas(*position, camera, camera_position, canvas) {
+            canvas.set_symbol(Vector2::new(x, y), symbol.clone());
+        }
+    }
+}
 
-            if let Some((x, y)) = world_to_canvas(position, &camera_position, &canvas) {
-                let symbol = get_canvas_symbol_for_appearence(appearence);
+/// Draws particles on top of everything else. Only the character and foreground are changed, so what's underneath
+/// still shows through as the background
+fn draw_particles(particles: &[&Particle], camera: &Camera, camera_position: &Position, canvas: &mut TextCanvas) {
+    for particle in particles {
+        let position = particle.current_position();
 
-                canvas.set_symbol(Vector2::new(x, y), symbol);
-            }
+        if let Some((x, y)) = world_to_canvas((position[0], position[1]), camera, camera_position, canvas) {
+            let location = Vector2::new(x, y);
+            canvas.set_character(location, particle.current_icon());
+            canvas.set_fg_color(location, particle.current_color());
         }
+    }
 }
 
-/// Returns `CanvasSymbol` that corresponds to the `Tile` of a tilemap
+/// Returns `CanvasSymbol` that corresponds to the `Tile` of a tilemap, drawn with `tile_font`
 /// TODO should move to another module?
-fn get_canvas_symbol_for_tile(tile: &Tile) -> CanvasSymbol {
-    match tile {
-        Tile::Blank => CanvasSymbol {
-            character: ' ',
-            foreground: Color::Black,
-            background: Color::Black,
-            modifiers: vec!()
-        },
-        Tile::Wall => CanvasSymbol {
-            character: '#',
-            foreground: Color::Black,
-            background: Color::White,
-            modifiers: vec!()
-        }
+fn get_canvas_symbol_for_tile(tile: &Tile, theme: &Theme, tile_font: TileFont) -> CanvasSymbol {
+    let (character, role) = match (tile, tile_font) {
+        (Tile::Blank, TileFont::Ascii) => (' ', ColorRole::Floor),
+        (Tile::Blank, TileFont::Unicode) | (Tile::Blank, TileFont::Box) => ('·', ColorRole::Floor),
+        (Tile::Wall, TileFont::Ascii) => ('#', ColorRole::Wall),
+        // Box walls are joined up by `get_symbol_for_location`, which knows the wall's neighbours
+        (Tile::Wall, TileFont::Unicode) | (Tile::Wall, TileFont::Box) => ('▒', ColorRole::Wall)
+    };
+    let colors = theme.colors(role);
+
+    CanvasSymbol {
+        character,
+        foreground: colors.foreground,
+        background: colors.background,
+        modifiers: vec!()
     }
 }
 
-fn get_canvas_symbol_for_appearence(appearence: &Appearance) -> CanvasSymbol {
+fn get_canvas_symbol_for_appearence(appearence: &Appearance, theme: &Theme) -> CanvasSymbol {
+    let (foreground, background) = match appearence.role {
+        Some(role) => {
+            let colors = theme.colors(role);
+            (colors.foreground, colors.background)
+        },
+        None => (appearence.foreground, appearence.background)
+    };
+
     CanvasSymbol {
         character: appearence.icon,
-        foreground: appearence.foreground,
-        background: appearence.background,
+        foreground,
+        background,
         modifiers: appearence.modifiers.clone()
     }
 }
@@ -135,21 +248,22 @@ fn get_canvas_symbol_for_appearence(appearence: &Appearance) -> CanvasSymbol {
 /// # Arguements
 ///
 /// * `xy` - (x, y) tuple in world space
+/// * `camera` - camera drawing onto the canvas, whose scale is the number of tiles per canvas cell
 /// * `camera_center` - location in world space of where the camera is centered
-/// * `canvas_dimension` - (width, height) of the canvas
+/// * `canvas` - canvas the camera draws onto
 fn world_to_canvas(
     xy: (i32, i32),
+    camera: &Camera,
     camera_center: &Position,
     canvas: &TextCanvas
 ) -> Option<(usize, usize)> {
-    let (canvas_width, canvas_height) = match canvas.dimensions() {
-        (w, h) => (conversions::as_i32(w), conversions::as_i32(h))
-    };
+    let (w, h) = canvas.dimensions();
     let (center_x, center_y) = (camera_center.vec2[0], camera_center.vec2[1]);
+    let scale = conversions::as_i32(camera.scale.max(1));
     let (x, y) = xy;
 
-    let canvas_x = x - center_x + (canvas_width / 2);
-    let canvas_y = y - center_y + (canvas_height / 2);
+    let canvas_x = (x - center_x + camera.half_view(w)).div_euclid(scale);
+    let canvas_y = (y - center_y + camera.half_view(h)).div_euclid(scale);
 
     if canvas.in_bounds(canvas_x, canvas_y) {
         Some((conversions::as_usize(canvas_x), conversions::as_usize(canvas_y)))
@@ -158,19 +272,28 @@ fn world_to_canvas(
     }
 }
 
-/// Returns tuple of the four corners/extreeme x,y values of the area a camera sees
-/// Returns in form: (min x, max x, min y, max y)
+/// Converts (x, y) in text_canvas space to (x, y) in world space. The inverse of `world_to_canvas`.
+/// When the camera's scale is larger than 1, returns the top-left of the block of tiles the canvas cell covers
 ///
 /// # Arguements
-/// * `camera_position` - positon the camera is centered at
-/// * `canvas_dimension` - (width, height) of canvas
-fn get_camera_corners(camera_position: &Position, canvas_dimensions: (usize, usize)) -> (i32, i32, i32, i32) {
-    let (width, height) = canvas_dimensions;
+///
+/// * `xy` - (x, y) tuple in text_canvas space, where (0, 0) is the top-left of the canvas
+/// * `camera` - camera drawing onto the canvas
+/// * `camera_center` - location in world space of where the camera is centered
+/// * `canvas` - canvas the camera draws onto
+pub fn canvas_to_world(
+    xy: (usize, usize),
+    camera: &Camera,
+    camera_center: &Position,
+    canvas: &TextCanvas
+) -> (i32, i32) {
+    let (w, h) = canvas.dimensions();
+    let (center_x, center_y) = (camera_center.vec2[0], camera_center.vec2[1]);
+    let scale = conversions::as_i32(camera.scale.max(1));
+    let (x, y) = (conversions::as_i32(xy.0), conversions::as_i32(xy.1));
 
-    let start_x: i32 = camera_position.vec2[0] - conversions::as_i32(width / 2);
-    let end_x: i32 = camera_position.vec2[0] + conversions::as_i32(width / 2);
-    let start_y: i32 =  camera_position.vec2[1] - conversions::as_i32(height / 2);
-    let end_y: i32 =  camera_position.vec2[1] + conversions::as_i32(height / 2);
+    let world_x = x * scale - camera.half_view(w) + center_x;
+    let world_y = y * scale - camera.half_view(h) + center_y;
 
-    (start_x, end_x, start_y, end_y)
+    (world_x, world_y)
 }
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use specs::{System, SystemData, Read};
+
+/// How long each `Timed` system took the last time it ran, by name. Behind a lock since systems that run in parallel
+/// record into it at the same time
+#[derive(Default, Debug)]
+pub struct SystemTimes {
+    times: Mutex<HashMap<&'static str, Duration>>,
+}
+
+impl SystemTimes {
+
+    /// Records that the system called `name` took `time` to run
+    pub fn record(&self, name: &'static str, time: Duration) {
+        if let Ok(mut times) = self.times.lock() {
+            times.insert(name, time);
+        }
+    }
+
+    /// Returns the time each system took to run, sorted by name
+    pub fn all(&self) -> Vec<(&'static str, Duration)> {
+        let mut times: Vec<(&'static str, Duration)> = match self.times.lock() {
+            Ok(times) => times.iter().map(|(&name, &time)| (name, time)).collect(),
+            Err(_) => vec!()
+        };
+        times.sort();
+
+        times
+    }
+
+}
+
+/// Runs `system`, recording how long it took in `SystemTimes` under `name`
+pub struct Timed<S> {
+    name: &'static str,
+    system: S,
+}
+
+impl<S> Timed<S> {
+
+    pub fn new(name: &'static str, system: S) -> Self {
+        Timed { name, system }
+    }
+
+}
+
+impl<'a, S> System<'a> for Timed<S> where S: System<'a>, S::SystemData: SystemData<'a> {
+    type SystemData = (S::SystemData, Read<'a, SystemTimes>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (data, times) = data;
+
+        let start = Instant::now();
+        self.system.run(data);
+        times.record(self.name, start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use specs::{RunNow, World, WorldExt};
+
+    struct SlowSystem;
+
+    impl<'a> System<'a> for SlowSystem {
+        type SystemData = ();
+
+        fn run(&mut self, _: Self::SystemData) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_records_time() {
+        let mut world = World::new();
+        world.insert(SystemTimes::default());
+
+        Timed::new("Slow", SlowSystem).run_now(&world);
+
+        let times = world.read_resource::<SystemTimes>().all();
+        assert_eq!(times.len(), 1);
+        assert_eq!(times[0].0, "Slow");
+        assert!(times[0].1 >= Duration::from_millis(5));
+    }
+}
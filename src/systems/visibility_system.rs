@@ -0,0 +1,93 @@
+use specs::{System, Write, ReadStorage, Join};
+use crate::entities::component::{Position, Viewshed, PositionAnimation, AnimationProgress};
+use crate::utility::geometry::line;
+use crate::world::map::Map;
+
+/// System that marks which tiles of the map can be seen by entities with a `Viewshed`, from where each is drawn.
+/// If nothing has a `Viewshed`, the whole map is visible
+pub struct VisibilitySystem;
+
+impl<'a> System<'a> for VisibilitySystem {
+    type SystemData = (
+        Write<'a, Map>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, PositionAnimation>,
+        ReadStorage<'a, AnimationProgress>,
+    );
+
+    fn run(&mut self, (mut map, position, viewshed, movements, progress): Self::SystemData) {
+        let mut viewers = (&position, &viewshed, movements.maybe(), progress.maybe()).join().peekable();
+
+        if viewers.peek().is_none() {
+            map.reveal_all();
+            return;
+        }
+
+        map.clear_visible();
+
+        for (position, viewshed, movement, progress) in viewers {
+            let center = position.drawn(movement, progress);
+            reveal_around(&mut map, (center[0], center[1]), viewshed.range);
+        }
+    }
+}
+
+/// Reveals every tile within `range` of `center` that has an unblocked line of sight to `center`.
+/// Tiles that block sight are revealed themselves, but hide what's behind them
+fn reveal_around(map: &mut Map, center: (i32, i32), range: i32) {
+    let (center_x, center_y) = center;
+
+    for x in (center_x - range)..=(center_x + range) {
+        for y in (center_y - range)..=(center_y + range) {
+            let (dx, dy) = (x - center_x, y - center_y);
+            if dx * dx + dy * dy > range * range || !map.in_bounds(x, y) {
+                continue;
+            }
+
+            if has_line_of_sight(map, center, (x, y)) {
+                map.reveal(x, y);
+            }
+        }
+    }
+}
+
+/// Returns `true` if no tile between `from` and `to` (not including either) blocks sight
+fn has_line_of_sight(map: &Map, from: (i32, i32), to: (i32, i32)) -> bool {
+    let line = line(from, to);
+
+    line.iter()
+        .skip(1)
+        .take(line.len().saturating_sub(2))
+        .all(|&(x, y)| !map.blocks_sight(x, y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::map::Tile;
+
+    #[test]
+    fn test_walls_block_sight() {
+        let mut map = Map::new(10, 3);
+        map.set(4, 1, Tile::Wall);
+
+        reveal_around(&mut map, (1, 1), 8);
+
+        assert!(map.is_visible(3, 1));
+        assert!(map.is_visible(4, 1));
+        assert!(!map.is_visible(5, 1));
+        assert!(!map.is_revealed(8, 1));
+    }
+
+    #[test]
+    fn test_remembers_revealed() {
+        let mut map = Map::new(10, 1);
+
+        reveal_around(&mut map, (0, 0), 3);
+        map.clear_visible();
+
+        assert!(!map.is_visible(2, 0));
+        assert!(map.is_revealed(2, 0));
+    }
+}
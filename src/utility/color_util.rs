@@ -1,6 +1,32 @@
+use std::str::FromStr;
 use tui::style::Color;
 
+/// The 16 named colors, in the order of their xterm color indices 0 to 15
+const NAMED_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Intensities of each channel used by the 6x6x6 color cube (xterm colors 16 to 231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
 /// Returns `color` as an `Color::Rgb` enum.
+///
+/// `Color::Reset` is the terminal's default color, which can't be known, so it is treated as black
 pub fn color_to_rgb(color: Color) -> Color {
     match color {
         // Dark colors
@@ -20,17 +46,260 @@ pub fn color_to_rgb(color: Color) -> Color {
         Color::LightMagenta => Color::Rgb(255, 118, 255),
         Color::LightCyan => Color::Rgb(95, 253, 255),
         // Grays
-        Color::Gray => todo!(),
-        Color::DarkGray => todo!(),
+        Color::Gray => Color::Rgb(199, 199, 199),
+        Color::DarkGray => Color::Rgb(103, 103, 103),
         // Non color constants
         Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
-        Color::Reset => todo!(),
-        Color::Indexed(_) => {
-            todo!()
+        Color::Reset => Color::Rgb(0, 0, 0),
+        Color::Indexed(index) => indexed_to_rgb(index)
+    }
+}
+
+/// Returns the xterm-256 color at `index` as a `Color::Rgb`
+///
+/// - 0 to 15 are the named colors
+/// - 16 to 231 are a 6x6x6 color cube
+/// - 232 to 255 are a grayscale ramp from dark to light
+fn indexed_to_rgb(index: u8) -> Color {
+    match index {
+        0..=15 => color_to_rgb(NAMED_COLORS[usize::from(index)]),
+        16..=231 => {
+            let cube_index = index - 16;
+            let r = CUBE_LEVELS[usize::from(cube_index / 36)];
+            let g = CUBE_LEVELS[usize::from((cube_index / 6) % 6)];
+            let b = CUBE_LEVELS[usize::from(cube_index % 6)];
+
+            Color::Rgb(r, g, b)
+        },
+        232..=255 => {
+            let level = 8 + (index - 232) * 10;
+            Color::Rgb(level, level, level)
+        }
+    }
+}
+
+/// Returns `color` as the closest `Color::Indexed` from the xterm-256 color cube and grayscale ramp.
+///
+/// The first 16 indices are left out as terminals often change what those look like.
+/// `Color::Reset` is returned as is
+pub fn to_indexed(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Reset => return Color::Reset,
+        Color::Indexed(index) if index >= 16 => return color,
+        color => rgb_components(color)
+    };
+
+    // Closest color in the cube, found separately for each channel
+    let cube_r = closest_cube_level(r);
+    let cube_g = closest_cube_level(g);
+    let cube_b = closest_cube_level(b);
+    let cube_color = (CUBE_LEVELS[cube_r], CUBE_LEVELS[cube_g], CUBE_LEVELS[cube_b]);
+    let cube_index = 16 + 36 * cube_r + 6 * cube_g + cube_b;
+
+    // Closest gray in the grayscale ramp
+    let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + gray_step * 10;
+    let gray_index = 232 + usize::from(gray_step);
+
+    if distance((r, g, b), (gray_level, gray_level, gray_level)) < distance((r, g, b), cube_color) {
+        Color::Indexed(gray_index as u8)
+    } else {
+        Color::Indexed(cube_index as u8)
+    }
+}
+
+/// Returns `color` as the closest of the 16 named colors (`Color::Black` to `Color::White`).
+///
+/// `Color::Reset` is returned as is
+pub fn to_named(color: Color) -> Color {
+    if color == Color::Reset {
+        return color;
+    }
+
+    let rgb = rgb_components(color);
+
+    NAMED_COLORS.iter()
+        .min_by_key(|&&named| distance(rgb, rgb_components(named)))
+        .copied()
+        .unwrap_or(Color::Reset)
+}
+
+/// How many colors the terminal can draw
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorDepth {
+    /// Any RGB color
+    TrueColor,
+    /// The xterm-256 palette
+    Indexed256,
+    /// Only the 16 named colors
+    Named16,
+}
+
+impl ColorDepth {
+
+    /// Detects the color depth of the terminal from the `COLORTERM` and `TERM` environment variables
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+
+        Self::from_env(colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Returns the color depth suggested by the values of the `COLORTERM` and `TERM` environment variables.
+    /// Falls back to 16 colors when unsure, as every color terminal supports them
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if let Some("truecolor") | Some("24bit") = colorterm {
+            return ColorDepth::TrueColor;
         }
+
+        match term {
+            Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+            Some(term) if term.contains("256color") => ColorDepth::Indexed256,
+            _ => ColorDepth::Named16
+        }
+    }
+
+}
+
+impl FromStr for ColorDepth {
+
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Indexed256),
+            "16" => Ok(ColorDepth::Named16),
+            other => Err(format!("Unknown color depth \"{}\", expected one of truecolor, 256 or 16", other))
+        }
+    }
+
+}
+
+/// Returns `color` as the closest color the terminal can draw with `depth`
+pub fn quantize(color: Color, depth: ColorDepth) -> Color {
+    match depth {
+        ColorDepth::TrueColor => color,
+        ColorDepth::Indexed256 => match color {
+            // Named colors are already in the palette
+            Color::Rgb(_, _, _) | Color::Indexed(_) => to_indexed(color),
+            color => color
+        },
+        ColorDepth::Named16 => to_named(color),
+    }
+}
+
+/// Quantizes a foreground and background color together.
+///
+/// When two different colors would become the same color, the foreground is changed to the next closest color so
+/// the symbol stays readable. This happens a lot with 16 colors, for example when shading makes a symbol darker
+pub fn quantize_pair(foreground: Color, background: Color, depth: ColorDepth) -> (Color, Color) {
+    let new_foreground = quantize(foreground, depth);
+    let new_background = quantize(background, depth);
+
+    let originally_distinct = rgb_components(foreground) != rgb_components(background);
+    if new_foreground != new_background || !originally_distinct || depth != ColorDepth::Named16 {
+        return (new_foreground, new_background);
+    }
+
+    let rgb = rgb_components(foreground);
+    let readable_foreground = NAMED_COLORS.iter()
+        .filter(|&&named| named != new_background)
+        .min_by_key(|&&named| distance(rgb, rgb_components(named)))
+        .copied()
+        .unwrap_or(new_foreground);
+
+    (readable_foreground, new_background)
+}
+
+/// Parses a color written as a name (`"red"`, `"lightblue"`, `"darkgray"`, `"reset"`), a hex code (`"#ff8800"`)
+/// or an xterm-256 index (`"208"`)
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim().to_lowercase();
+
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    if let Ok(index) = text.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+
+    let color = match text.replace(&['_', '-', ' '][..], "").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        "reset" | "default" => Color::Reset,
+        _ => return None
+    };
+
+    Some(color)
+}
+
+/// Returns the color `amount` of the way from `from` to `to`, where `amount` is clamped to 0.0 .. 1.0
+pub fn lerp(from: Color, to: Color, amount: f64) -> Color {
+    let (r, g, b) = rgb_components(from);
+    let (r_to, g_to, b_to) = rgb_components(to);
+    let amount = amount.clamp(0., 1.);
+
+    let channel = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+
+    Color::Rgb(channel(r, r_to), channel(g, g_to), channel(b, b_to))
+}
+
+/// Returns the xterm color index (0 to 15) of a named color, or `None` if `color` isn't one of the 16 named colors
+pub fn named_index(color: Color) -> Option<u8> {
+    NAMED_COLORS.iter().position(|&named| named == color).map(|index| index as u8)
+}
+
+/// Returns the `(r, g, b)` values of `color`
+pub fn rgb_components(color: Color) -> (u8, u8, u8) {
+    match color_to_rgb(color) {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (0, 0, 0)
     }
 }
 
+/// Returns the index of the level in `CUBE_LEVELS` that is closest to `value`
+fn closest_cube_level(value: u8) -> usize {
+    CUBE_LEVELS.iter()
+        .enumerate()
+        .min_by_key(|(_, &level)| (i32::from(level) - i32::from(value)).abs())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Returns the squared perceptual distance between two colors.
+/// Uses the "redmean" weighting, which is cheap and closer to how eyes see differences than plain RGB distance
+fn distance(former: (u8, u8, u8), latter: (u8, u8, u8)) -> u32 {
+    let (r1, g1, b1) = (i64::from(former.0), i64::from(former.1), i64::from(former.2));
+    let (r2, g2, b2) = (i64::from(latter.0), i64::from(latter.1), i64::from(latter.2));
+
+    let red_mean = (r1 + r2) / 2;
+    let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+
+    let weighted = (((512 + red_mean) * dr * dr) >> 8) + 4 * dg * dg + (((767 - red_mean) * db * db) >> 8);
+    weighted as u32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +330,109 @@ mod tests {
         let white = Color::White;
         assert_eq!(color_to_rgb(white), Color::Rgb(254, 255, 255));
     }
+
+    #[test]
+    fn test_grays() {
+        assert_eq!(color_to_rgb(Color::Gray), Color::Rgb(199, 199, 199));
+        assert_eq!(color_to_rgb(Color::DarkGray), Color::Rgb(103, 103, 103));
+    }
+
+    #[test]
+    fn test_reset() {
+        assert_eq!(color_to_rgb(Color::Reset), Color::Rgb(0, 0, 0));
+        assert_eq!(to_indexed(Color::Reset), Color::Reset);
+        assert_eq!(to_named(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn test_indexed_colors() {
+        // Named colors
+        assert_eq!(color_to_rgb(Color::Indexed(1)), color_to_rgb(Color::Red));
+        assert_eq!(color_to_rgb(Color::Indexed(15)), color_to_rgb(Color::White));
+
+        // Color cube
+        assert_eq!(color_to_rgb(Color::Indexed(16)), Color::Rgb(0, 0, 0));
+        assert_eq!(color_to_rgb(Color::Indexed(196)), Color::Rgb(255, 0, 0));
+        assert_eq!(color_to_rgb(Color::Indexed(110)), Color::Rgb(135, 175, 215));
+        assert_eq!(color_to_rgb(Color::Indexed(231)), Color::Rgb(255, 255, 255));
+
+        // Grayscale
+        assert_eq!(color_to_rgb(Color::Indexed(232)), Color::Rgb(8, 8, 8));
+        assert_eq!(color_to_rgb(Color::Indexed(255)), Color::Rgb(238, 238, 238));
+    }
+
+    #[test]
+    fn test_to_indexed() {
+        // Exact matches map back to themselves
+        for index in 16..=255 {
+            assert_eq!(to_indexed(Color::Indexed(index)), Color::Indexed(index));
+            assert_eq!(to_indexed(indexed_to_rgb(index)), Color::Indexed(index));
+        }
+
+        assert_eq!(to_indexed(Color::Rgb(250, 5, 10)), Color::Indexed(196));
+        assert_eq!(to_indexed(Color::Rgb(50, 50, 52)), Color::Indexed(236));
+    }
+
+    #[test]
+    fn test_to_named() {
+        assert_eq!(to_named(Color::Rgb(250, 5, 10)), Color::Red);
+        assert_eq!(to_named(Color::Rgb(10, 10, 10)), Color::Black);
+        assert_eq!(to_named(Color::Rgb(120, 120, 120)), Color::DarkGray);
+        assert_eq!(to_named(Color::Rgb(130, 255, 130)), Color::LightGreen);
+        assert_eq!(to_named(Color::Indexed(231)), Color::White);
+    }
+
+    #[test]
+    fn test_detect_depth() {
+        assert_eq!(ColorDepth::from_env(Some("truecolor"), Some("xterm")), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::from_env(None, Some("xterm-direct")), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::from_env(None, Some("tmux-256color")), ColorDepth::Indexed256);
+        assert_eq!(ColorDepth::from_env(None, Some("screen")), ColorDepth::Named16);
+        assert_eq!(ColorDepth::from_env(None, Some("linux")), ColorDepth::Named16);
+        assert_eq!(ColorDepth::from_env(None, None), ColorDepth::Named16);
+    }
+
+    #[test]
+    fn test_quantize() {
+        let color = Color::Rgb(250, 5, 10);
+
+        assert_eq!(quantize(color, ColorDepth::TrueColor), color);
+        assert_eq!(quantize(color, ColorDepth::Indexed256), Color::Indexed(196));
+        assert_eq!(quantize(color, ColorDepth::Named16), Color::Red);
+        assert_eq!(quantize(Color::Cyan, ColorDepth::Indexed256), Color::Cyan);
+    }
+
+    #[test]
+    fn test_quantize_pair_stays_readable() {
+        let foreground = Color::Rgb(40, 40, 40);
+        let background = Color::Rgb(0, 0, 0);
+
+        let (new_foreground, new_background) = quantize_pair(foreground, background, ColorDepth::Named16);
+        assert_eq!(new_background, Color::Black);
+        assert_eq!(new_foreground, Color::DarkGray);
+
+        // Same colors are left the same
+        assert_eq!(quantize_pair(background, background, ColorDepth::Named16), (Color::Black, Color::Black));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("red"), Some(Color::Red));
+        assert_eq!(parse_color("Light_Blue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("dark-grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("reset"), Some(Color::Reset));
+        assert_eq!(parse_color("#E69F00"), Some(Color::Rgb(230, 159, 0)));
+        assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("chartreuse"), None);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let (from, to) = (Color::Rgb(0, 100, 200), Color::Rgb(100, 100, 0));
+
+        assert_eq!(lerp(from, to, 0.), from);
+        assert_eq!(lerp(from, to, 0.5), Color::Rgb(50, 100, 100));
+        assert_eq!(lerp(from, to, 2.), to);
+    }
 }
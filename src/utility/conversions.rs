@@ -2,10 +2,7 @@ use std::convert::TryInto;
 
 /// Converts `num` to `i32`, clamping it to `i32`'s max value if `num` is too big
 pub fn as_i32(num: usize) -> i32 {
-    match num.try_into() {
-        Ok(result) => result,
-        Err(_) => i32::MAX
-    }
+    num.try_into().unwrap_or(i32::MAX)
 }
 
 /// Converts `num` to `usize`, clamping it between 0 and `usize::MAX` if `num` is outside range
@@ -24,8 +21,10 @@ pub fn as_usize(num: i32) -> usize {
 
 /// Converts `num` to `usize`, clamping it between 0 and `u16;:MAX`
 pub fn u16_to_usize(num: u16) -> usize {
-    match num.try_into() {
-        Ok(result) => result,
-        Err(_) => u16::MAX.into()
-    }
+    usize::from(num)
+}
+
+/// Converts `num` to `u16`, clamping it to `u16`'s max value if `num` is too big
+pub fn usize_to_u16(num: usize) -> u16 {
+    num.try_into().unwrap_or(u16::MAX)
 }
@@ -0,0 +1,252 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Text};
+
+use crate::utility::color_util::{self, ColorDepth};
+use crate::utility::text_canvas::TextCanvas;
+
+/// SGR parameter of each modifier, in the order they are written
+const MODIFIER_CODES: [(Modifier, u8); 9] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// File format a screenshot can be saved as
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    /// Text with ANSI escape codes, that shows its colors when `cat`ed in a terminal
+    Ansi,
+    /// Web page with the colors as inline styles
+    Html,
+    /// Just the characters
+    Plain,
+}
+
+impl ExportFormat {
+
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Ansi, ExportFormat::Html, ExportFormat::Plain];
+
+    /// Returns the file extension for the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Ansi => "ans",
+            ExportFormat::Html => "html",
+            ExportFormat::Plain => "txt",
+        }
+    }
+
+}
+
+/// Returns `text` written out in `format`
+pub fn export(text: &Text, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Ansi => to_ansi(text),
+        ExportFormat::Html => to_html(text),
+        ExportFormat::Plain => to_plain(text),
+    }
+}
+
+/// Saves `canvas` in every `ExportFormat` to `directory`, creating it if needed. The files share a name made from
+/// the current time. Returns the paths of the saved files
+pub fn save_screenshot(
+    canvas: &TextCanvas,
+    color_depth: ColorDepth,
+    wide_glyphs: bool,
+    directory: &Path
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis()).unwrap_or(0);
+    let text = canvas.as_styled_text(color_depth, wide_glyphs);
+
+    ExportFormat::ALL.iter().map(|&format| {
+        let path = directory.join(format!("screenshot-{}.{}", timestamp, format.extension()));
+        fs::write(&path, export(&text, format))?;
+        Ok(path)
+    }).collect()
+}
+
+/// Iterates over the spans of each line, leaving out the newlines `TextCanvas` ends its lines with
+fn lines<'a>(text: &'a Text) -> impl Iterator<Item = impl Iterator<Item = &'a Span<'a>>> {
+    text.lines.iter().map(|line| line.0.iter().filter(|span| span.content != "\n"))
+}
+
+fn to_plain(text: &Text) -> String {
+    let mut output = String::new();
+    for line in lines(text) {
+        for span in line {
+            output.push_str(&span.content);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn to_ansi(text: &Text) -> String {
+    let mut output = String::new();
+    for line in lines(text) {
+        let mut current = None;
+        for span in line {
+            // Only write the style when it changes, as most neighbouring cells look the same
+            if current != Some(span.style) {
+                output.push_str(&ansi_style(span.style));
+                current = Some(span.style);
+            }
+            output.push_str(&span.content);
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+/// Returns the escape code that resets the terminal's style then sets it to `style`
+fn ansi_style(style: Style) -> String {
+    let mut codes = vec!("0".to_string());
+
+    for &(modifier, code) in MODIFIER_CODES.iter() {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    if let Some(color) = style.fg {
+        codes.push(ansi_color(color, false));
+    }
+    if let Some(color) = style.bg {
+        codes.push(ansi_color(color, true));
+    }
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// Returns the SGR parameters that set the foreground, or background if `background` is `true`, to `color`
+fn ansi_color(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+
+    let named = |index: u8| {
+        if index < 8 { base + index } else { base + 60 + index - 8 }
+    };
+
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        Color::Indexed(index) => format!("{};5;{}", base + 8, index),
+        named_color => match color_util::named_index(named_color) {
+            Some(index) => named(index).to_string(),
+            None => (base + 9).to_string()
+        }
+    }
+}
+
+fn to_html(text: &Text) -> String {
+    let mut output = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>terminal-dungeon</title></head>\n\
+         <body style=\"background-color: #000000; color: #c7c7c7\">\n<pre style=\"font-family: monospace\">\n"
+    );
+
+    for line in lines(text) {
+        for span in line {
+            let _ = write!(output, "<span style=\"{}\">{}</span>", css_style(span.style), escape_html(&span.content));
+        }
+        output.push('\n');
+    }
+
+    output.push_str("</pre>\n</body>\n</html>\n");
+    output
+}
+
+/// Returns `style` as inline CSS
+fn css_style(style: Style) -> String {
+    let modifiers = style.add_modifier;
+    let (mut foreground, mut background) = (style.fg, style.bg);
+    if modifiers.contains(Modifier::REVERSED) {
+        std::mem::swap(&mut foreground, &mut background);
+    }
+
+    let mut css = Vec::new();
+    if let Some(color) = foreground.filter(|&color| color != Color::Reset) {
+        css.push(format!("color: {}", css_color(color)));
+    }
+    if let Some(color) = background.filter(|&color| color != Color::Reset) {
+        css.push(format!("background-color: {}", css_color(color)));
+    }
+    if modifiers.contains(Modifier::BOLD) {
+        css.push("font-weight: bold".to_string());
+    }
+    if modifiers.contains(Modifier::DIM) {
+        css.push("opacity: 0.5".to_string());
+    }
+    if modifiers.contains(Modifier::ITALIC) {
+        css.push("font-style: italic".to_string());
+    }
+    if modifiers.intersects(Modifier::UNDERLINED | Modifier::CROSSED_OUT) {
+        let mut lines = Vec::new();
+        if modifiers.contains(Modifier::UNDERLINED) {
+            lines.push("underline");
+        }
+        if modifiers.contains(Modifier::CROSSED_OUT) {
+            lines.push("line-through");
+        }
+        css.push(format!("text-decoration: {}", lines.join(" ")));
+    }
+    if modifiers.contains(Modifier::HIDDEN) {
+        css.push("visibility: hidden".to_string());
+    }
+
+    css.join("; ")
+}
+
+/// Returns `color` as a CSS hex color
+fn css_color(color: Color) -> String {
+    let (r, g, b) = color_util::rgb_components(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tui::text::Spans;
+
+    fn sample() -> Text<'static> {
+        Text::from(vec!(
+            Spans::from(vec!(
+                Span::styled("#", Style::default().fg(Color::Black).bg(Color::White)),
+                Span::styled("<", Style::default().fg(Color::Rgb(255, 0, 0)).add_modifier(Modifier::BOLD)),
+                Span::raw("\n")
+            )),
+            Spans::from(vec!(Span::raw("@"), Span::raw("\n")))
+        ))
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(export(&sample(), ExportFormat::Plain), "#<\n@\n");
+    }
+
+    #[test]
+    fn test_ansi() {
+        let ansi = export(&sample(), ExportFormat::Ansi);
+        assert_eq!(ansi, "\x1b[0;30;107m#\x1b[0;1;38;2;255;0;0m<\x1b[0m\n\x1b[0m@\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_html() {
+        let html = export(&sample(), ExportFormat::Html);
+        assert!(html.contains("<span style=\"color: #000000; background-color: #feffff\">#</span>"));
+        assert!(html.contains("<span style=\"color: #ff0000; font-weight: bold\">&lt;</span>"));
+    }
+}
@@ -0,0 +1,39 @@
+/// Returns the tiles on the line from `from` to `to`, including both ends, using Bresenham's line algorithm
+pub fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let (end_x, end_y) = to;
+
+    let dx = (end_x - x).abs();
+    let dy = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut points = vec!((x, y));
+    while (x, y) != (end_x, end_y) {
+        let doubled_error = 2 * error;
+        if doubled_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+        points.push((x, y));
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(line((0, 0), (3, 0)), vec!((0, 0), (1, 0), (2, 0), (3, 0)));
+        assert_eq!(line((0, 0), (-2, -2)), vec!((0, 0), (-1, -1), (-2, -2)));
+        assert_eq!(line((1, 1), (1, 1)), vec!((1, 1)));
+    }
+}
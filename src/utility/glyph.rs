@@ -0,0 +1,70 @@
+use std::str::FromStr;
+use unicode_width::UnicodeWidthChar;
+
+/// Drawn in place of a glyph that can't be shown in the space it has
+pub const FALLBACK_GLYPH: char = '?';
+
+/// Returns the number of terminal columns `character` takes up: 2 for wide glyphs like CJK and most emoji, 1 for
+/// most other characters, and 0 for combining marks and control characters
+pub fn width(character: char) -> usize {
+    character.width().unwrap_or(0)
+}
+
+/// Returns `character` if it can be drawn in `columns` terminal columns, otherwise `FALLBACK_GLYPH`.
+///
+/// Characters of width 0 are replaced too, as a combining mark on its own would merge into the cell before it
+pub fn fit(character: char, columns: usize) -> char {
+    match width(character) {
+        0 => FALLBACK_GLYPH,
+        n if n > columns => FALLBACK_GLYPH,
+        _ => character
+    }
+}
+
+/// Set of characters map tiles are drawn with
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum TileFont {
+    /// Plain ASCII that every terminal can draw
+    #[default]
+    Ascii,
+    /// Shading and symbols from Unicode, which need a font that has them
+    Unicode,
+    /// Like `Unicode`, but walls are box drawing characters that join up with the walls next to them
+    Box,
+}
+
+impl FromStr for TileFont {
+
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(TileFont::Ascii),
+            "unicode" => Ok(TileFont::Unicode),
+            "box" => Ok(TileFont::Box),
+            other => Err(format!("Unknown tile font \"{}\", expected ascii, unicode or box", other))
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width('@'), 1);
+        assert_eq!(width('竜'), 2);
+        assert_eq!(width('🐉'), 2);
+        assert_eq!(width('\u{301}'), 0);
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit('@', 1), '@');
+        assert_eq!(fit('🐉', 2), '🐉');
+        assert_eq!(fit('🐉', 1), FALLBACK_GLYPH);
+        assert_eq!(fit('\u{301}', 2), FALLBACK_GLYPH);
+    }
+}
@@ -0,0 +1,229 @@
+use std::ops::{Index, IndexMut};
+use crate::utility::conversions;
+
+/// Offsets of the 4 orthogonal neighbours of a cell: up, down, left, right
+const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// Offsets of all 8 cells surrounding a cell, clockwise starting from the top-left
+const SURROUNDING: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)];
+
+/// Fixed size 2D grid stored as a single contiguous row-major buffer
+///
+/// Cell `(x, y)` lives at `y * width + x`, so iterating a row touches adjacent memory
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>
+}
+
+impl<T> Default for Grid<T> {
+
+    fn default() -> Self {
+        Grid { width: 0, height: 0, cells: Vec::new() }
+    }
+
+}
+
+impl<T: Clone> Grid<T> {
+
+    /// Returns `Grid` of size `width` x `height` with every cell set to `fill`
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid { width, height, cells: vec![fill; width * height] }
+    }
+
+    /// Sets every cell to `value`
+    pub fn fill(&mut self, value: T) {
+        for cell in &mut self.cells {
+            *cell = value.clone();
+        }
+    }
+
+}
+
+impl<T> Grid<T> {
+
+    /// Returns `(width, height)` of the grid
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns `true` if (x, y) is in bounds of the grid
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        (x >= 0 && x < conversions::as_i32(self.width)) && (y >= 0 && y < conversions::as_i32(self.height))
+    }
+
+    /// Returns the cell at (x, y), or `None` if (x, y) is out of bounds
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.index_of(x, y).map(|i| &self.cells[i])
+    }
+
+    /// Returns a mutable reference to the cell at (x, y), or `None` if (x, y) is out of bounds
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        self.index_of(x, y).map(move |i| &mut self.cells[i])
+    }
+
+    /// Sets the cell at (x, y) to `value`. Does nothing if (x, y) is out of bounds
+    pub fn set(&mut self, x: i32, y: i32, value: T) {
+        if let Some(cell) = self.get_mut(x, y) {
+            *cell = value;
+        }
+    }
+
+    /// Iterates over each row of the grid from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `max(1)` as `chunks` panics on 0, which happens for empty grids
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Iterates over every cell as `(x, y, cell)`, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width.max(1);
+        self.cells.iter().enumerate().map(move |(i, cell)| (i % width, i / width, cell))
+    }
+
+    /// Iterates over the cells in the `width` x `height` rectangle with its top-left at (x, y), row by row, as
+    /// `(x, y, cell)`. The parts of the rectangle that are out of bounds are skipped
+    pub fn rect(&self, x: i32, y: i32, width: usize, height: usize) -> impl Iterator<Item = (usize, usize, &T)> {
+        let start_x = conversions::as_usize(x);
+        let start_y = conversions::as_usize(y);
+        let end_x = clamp_end(x, width, self.width);
+        let end_y = clamp_end(y, height, self.height);
+
+        (start_y..end_y).flat_map(move |j| {
+            (start_x..end_x).map(move |i| (i, j, &self.cells[j * self.width + i]))
+        })
+    }
+
+    /// Iterates over the in bounds orthogonal neighbours (up, down, left, right) of (x, y) as `(x, y, cell)`
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32, &T)> {
+        self.offset_cells(x, y, &ORTHOGONAL)
+    }
+
+    /// Iterates over all 8 in bounds cells surrounding (x, y) as `(x, y, cell)`
+    pub fn surrounding(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32, &T)> {
+        self.offset_cells(x, y, &SURROUNDING)
+    }
+
+    fn offset_cells<'a>(
+        &'a self,
+        x: i32,
+        y: i32,
+        offsets: &'static [(i32, i32)]
+    ) -> impl Iterator<Item = (i32, i32, &'a T)> {
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            self.get(x + dx, y + dy).map(|cell| (x + dx, y + dy, cell))
+        })
+    }
+
+    /// Returns index into `self.cells` for (x, y), or `None` if (x, y) is out of bounds
+    fn index_of(&self, x: i32, y: i32) -> Option<usize> {
+        if self.in_bounds(x, y) {
+            Some(conversions::as_usize(y) * self.width + conversions::as_usize(x))
+        } else {
+            None
+        }
+    }
+
+}
+
+/// Returns the exclusive end of a span starting at `start` of length `length`, clamped to `0..=limit`
+fn clamp_end(start: i32, length: usize, limit: usize) -> usize {
+    let end = i64::from(start) + length as i64;
+    end.clamp(0, limit as i64) as usize
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+
+    type Output = T;
+
+    /// Returns the cell at `(x, y)`. Panics if out of bounds
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        assert!(x < self.width && y < self.height, "({}, {}) is out of bounds of grid", x, y);
+        &self.cells[y * self.width + x]
+    }
+
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+
+    /// Returns the cell at `(x, y)`. Panics if out of bounds
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        assert!(x < self.width && y < self.height, "({}, {}) is out of bounds of grid", x, y);
+        &mut self.cells[y * self.width + x]
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 3x2 grid where each cell holds `x + 10 * y`
+    fn numbered_grid() -> Grid<i32> {
+        let mut grid = Grid::new(3, 2, 0);
+        for y in 0..2 {
+            for x in 0..3 {
+                grid.set(x, y, x + 10 * y);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_get() {
+        let grid = numbered_grid();
+
+        assert_eq!(grid.get(0, 0), Some(&0));
+        assert_eq!(grid.get(2, 1), Some(&12));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid.get(-1, 0), None);
+        assert_eq!(grid[(1, 1)], 11);
+    }
+
+    #[test]
+    fn test_set_out_of_bounds() {
+        let mut grid = numbered_grid();
+        grid.set(5, 5, 100);
+        grid.set(-1, 0, 100);
+
+        assert!(grid.iter().all(|(_, _, &cell)| cell != 100));
+    }
+
+    #[test]
+    fn test_rows() {
+        let grid = numbered_grid();
+        let rows: Vec<&[i32]> = grid.rows().collect();
+
+        assert_eq!(rows, vec!(&[0, 1, 2][..], &[10, 11, 12][..]));
+    }
+
+    #[test]
+    fn test_empty() {
+        let grid: Grid<i32> = Grid::default();
+
+        assert_eq!(grid.dimensions(), (0, 0));
+        assert_eq!(grid.rows().count(), 0);
+        assert_eq!(grid.get(0, 0), None);
+    }
+
+    #[test]
+    fn test_rect_is_clipped() {
+        let grid = numbered_grid();
+        let cells: Vec<i32> = grid.rect(-1, 1, 3, 5).map(|(_, _, &cell)| cell).collect();
+
+        assert_eq!(cells, vec!(10, 11));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = numbered_grid();
+
+        let corner: Vec<i32> = grid.neighbours(0, 0).map(|(_, _, &cell)| cell).collect();
+        assert_eq!(corner, vec!(10, 1));
+
+        let surrounding: Vec<i32> = grid.surrounding(1, 0).map(|(_, _, &cell)| cell).collect();
+        assert_eq!(surrounding, vec!(2, 12, 11, 10, 0));
+    }
+}
@@ -7,8 +7,10 @@ type Result<T> = std::result::Result<T, IconSplineConstructorError>;
 
 /// Spline specifically for setting icons in a stepwise manner
 ///
-/// Uses `f32` under the hood to interpolate, then casts result to `u8`, then to `char`
-struct IconSpline {
+/// Uses `f32` under the hood to interpolate, then casts result to `u8`, then to `char`, so only characters up to
+/// `'\u{ff}'` can be used
+#[derive(Clone)]
+pub struct IconSpline {
     spline: Spline<f32, f32>,
 }
 
@@ -17,31 +19,31 @@ impl IconSpline {
     ///
     /// `icon_range` is a Vector of tuples that contains each key for the spline.
     /// Each tuple has `(amount, character)` means that starting at `amount`, it should use `character`
-    fn new(mut icon_ranges: Vec<(f32, char)>) -> Result<Self> {
+    pub fn new(mut icon_ranges: Vec<(f32, char)>) -> Result<Self> {
         icon_ranges.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
-        let float_ranges = icon_ranges.into_iter().map(|(s, c)| (s, c as u8 as f32)).collect();
+        let float_ranges: Vec<(f32, f32)> = icon_ranges.into_iter().map(|(s, c)| (s, c as u8 as f32)).collect();
 
         if validate_ranges(&float_ranges) {
-            return Ok(IconSpline {
+            Ok(IconSpline {
                 spline: create_spline(float_ranges),
-            });
+            })
         } else {
-            return Err(IconSplineConstructorError(float_ranges));
+            Err(IconSplineConstructorError(float_ranges))
         }
     }
 
-    fn sample(&self, point: f32) -> Option<char> {
-        self.spline.sample(point).and_then(|p| Some(p as u8 as char))
+    pub fn sample(&self, point: f32) -> Option<char> {
+        self.spline.sample(point).map(|p| p as u8 as char)
     }
 
-    fn clamped_sample(&self, point: f32) -> Option<char> {
-        self.spline.clamped_sample(point).and_then(|p| Some(p as u8 as char))
+    pub fn clamped_sample(&self, point: f32) -> Option<char> {
+        self.spline.clamped_sample(point).map(|p| p as u8 as char)
     }
 }
 
 /// Ensures that `icon_ranges` has entries between 0..1, and has one that starts at 0
-fn validate_ranges(icon_ranges: &Vec<(f32, f32)>) -> bool {
-    if icon_ranges.len() == 0 {
+fn validate_ranges(icon_ranges: &[(f32, f32)]) -> bool {
+    if icon_ranges.is_empty() {
         return false;
     }
 
@@ -51,7 +53,7 @@ fn validate_ranges(icon_ranges: &Vec<(f32, f32)>) -> bool {
 
     if icon_ranges
         .iter()
-        .any(|&(start, _)| start > 1. && start < 0.)
+        .any(|&(start, _)| !(0. ..=1.).contains(&start))
     {
         return false;
     }
@@ -63,7 +65,7 @@ fn create_spline(icon_ranges: Vec<(f32, f32)>) -> Spline<f32, f32> {
     let mut keys = Vec::new();
     let last_character = match icon_ranges.last() {
         Some(&(_, value)) => value,
-        None => '?' as u8 as f32
+        None => b'?' as f32
     };
 
     for (start, character) in icon_ranges {
@@ -76,7 +78,7 @@ fn create_spline(icon_ranges: Vec<(f32, f32)>) -> Spline<f32, f32> {
     Spline::from_vec(keys)
 }
 
-struct IconSplineConstructorError(Vec<(f32, f32)>);
+pub struct IconSplineConstructorError(Vec<(f32, f32)>);
 
 impl Error for IconSplineConstructorError {}
 
@@ -91,8 +93,8 @@ impl fmt::Display for IconSplineConstructorError {
 }
 
 impl fmt::Debug for IconSplineConstructorError {
-    fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        todo!()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IconSplineConstructorError({:?})", self.0)
     }
 }
 
@@ -130,12 +132,16 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_bad_new() {
         let empty_spline = IconSpline::new(vec![]);
         assert_eq!(empty_spline.is_ok(), false);
 
         let no_beginning_spline = IconSpline::new(vec![(0.5, '?'), (0.8, '!')]);
         assert_eq!(no_beginning_spline.is_ok(), false);
+
+        let out_of_range_spline = IconSpline::new(vec![(0., '.'), (1.5, '!')]);
+        assert_eq!(out_of_range_spline.is_ok(), false);
     }
 
     #[test]
@@ -177,6 +183,7 @@ mod test {
         assert_eq!(spline.sample(1.00), None);
     }
 
+    #[test]
     fn test_applying_clamped_splines() {
         let spline = IconSpline::new(vec![
             (0.0, '0'),
@@ -2,6 +2,10 @@ pub mod color_util;
 pub mod text_canvas;
 pub mod conversions;
 pub mod icon_spline;
+pub mod grid;
+pub mod glyph;
+pub mod export;
+pub mod geometry;
 
 #[cfg(test)]
 pub mod test_util;
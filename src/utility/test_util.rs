@@ -1,6 +1,16 @@
 use crossterm::event;
 use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::io;
 use std::time::Duration;
+use tui::Terminal;
+use tui::backend::TestBackend;
+use tui::buffer::Buffer;
+
+use crate::game::GameTick;
+use crate::views::Screen;
+use crate::views::screens::GameScreen;
 
 pub type TestResult = std::result::Result<(), Box<dyn Error>>;
 
@@ -16,3 +26,50 @@ pub fn clear_inputs(timeout: Duration) {
         let _ = event::read();
     }
 }
+
+/// Returns a 60x24 test terminal with a new game to draw on it, and the tick the game tests step it with
+pub fn game_screen_test() -> io::Result<(Terminal<TestBackend>, GameScreen<'static, TestBackend>, GameTick)> {
+    let terminal = Terminal::new(TestBackend::new(60, 24))?;
+    Ok((terminal, GameScreen::new(), GameTick::Tick(Duration::from_millis(200))))
+}
+
+/// Returns the characters drawn to `buffer`, with a line for each row
+pub fn buffer_to_string(buffer: &Buffer) -> String {
+    let width = usize::from(buffer.area.width).max(1);
+
+    buffer.content.chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>() + "\n")
+        .collect()
+}
+
+/// Asserts that `actual` matches the golden snapshot `name` in `src/views/snapshots`.
+///
+/// If `UPDATE_SNAPSHOTS` is set, `actual` is saved as the snapshot instead. A missing snapshot fails, so a golden file
+/// that was deleted or misnamed can't pass unnoticed
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/views/snapshots").join(format!("{}.txt", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(error) => panic!(
+            "Couldn't read snapshot {} ({}). Run with UPDATE_SNAPSHOTS=1 to save it\n\nactual:\n{}",
+            name,
+            error,
+            actual
+        )
+    };
+
+    assert!(
+        expected == actual,
+        "Snapshot {} doesn't match. Run with UPDATE_SNAPSHOTS=1 to update it\n\nexpected:\n{}\nactual:\n{}",
+        name,
+        expected,
+        actual
+    );
+}
@@ -1,118 +1,164 @@
+use specs::{Component, HashMapStorage};
 use tui::text::{Span, Spans, Text};
 use tui::style::{Color, Style, Modifier};
 use crate::world::map::Map;
-use crate::utility::{color_util, conversions};
+use crate::utility::{color_util, conversions, glyph};
+use crate::utility::color_util::ColorDepth;
+use crate::utility::grid::Grid;
 
 extern crate nalgebra as na;
 use na::{clamp, Vector2};
 
 /// Canvas that is used to generate a string to represent the portion of the map on camera
+///
+/// Each `Camera` entity owns the canvas it renders onto
+#[derive(Component, Default)]
+#[storage(HashMapStorage)]
 pub struct TextCanvas {
-    symbols: Vec<Vec<CanvasSymbol>>
-}
-
-impl Default for TextCanvas {
-
-    fn default() -> TextCanvas {
-        TextCanvas { symbols: vec![vec![CanvasSymbol::default(); 0]; 0] }
-    }
-
+    symbols: Grid<CanvasSymbol>
 }
 
 impl TextCanvas {
 
     /// Returns `TextCanvas` with default symbols of size `width` x `height`
     pub fn with_size(width: usize, height: usize) -> Self {
-        TextCanvas { symbols: vec![vec![CanvasSymbol::default(); height]; width] }
+        TextCanvas { symbols: Grid::new(width, height, CanvasSymbol::default()) }
     }
 
     /// Returns `TextCanvas` with default symbols of the same dimensions as the map
     pub fn for_map(map: &Map) -> Self {
         let (map_width, map_height) = map.dimensions();
-        TextCanvas { symbols: vec![vec![CanvasSymbol::default(); map_height]; map_width] }
+        Self::with_size(map_width, map_height)
     }
 
-    /// Returns representation of the canvas as a multiline `Text`
-    pub fn as_styled_text(&self) -> Text {
-        let mut map_lines: Vec<Spans> = vec!();
-        let (width, height) = self.dimensions();
+    /// Changes the size of the canvas to `width` x `height`. If the size changed, all symbols are reset to the default
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if self.dimensions() != (width, height) {
+            *self = Self::with_size(width, height);
+        }
+    }
+
+    /// Returns representation of the canvas as a multiline `Text`, with colors reduced to what can be drawn with
+    /// `color_depth`
+    ///
+    /// If `wide_glyphs` is `true`, a double width glyph takes up its own cell and the cell to its right, hiding
+    /// whatever is there. Otherwise, or if it's in the last column, it's replaced with `glyph::FALLBACK_GLYPH` so the
+    /// rows stay aligned
+    pub fn as_styled_text(&self, color_depth: ColorDepth, wide_glyphs: bool) -> Text<'_> {
+        let map_lines: Vec<Spans> = self.symbols.rows().map(|row| {
+            let mut line: Vec<Span> = Vec::with_capacity(row.len() + 1);
 
-        for i in 0..height {
-            let mut line: Vec<Span> = vec!();
+            let mut x = 0;
+            while x < row.len() {
+                let columns = if wide_glyphs { (row.len() - x).min(2) } else { 1 };
+                let character = glyph::fit(row[x].character, columns);
 
-            for j in 0..width {
-                let symbol = &self.symbols[j][i];
-                line.push(symbol.span());
+                line.push(row[x].span(character, color_depth));
+                x += glyph::width(character);
             }
             line.push(Span::raw("\n"));
 
-            map_lines.push(Spans::from(line));
-        }
+            Spans::from(line)
+        }).collect();
 
         Text::from(map_lines)
     }
 
     /// Returns `(width, height)` of the canvas
     pub fn dimensions(&self) -> (usize, usize) {
-        if self.symbols.len() > 0 {
-            (self.symbols.len(), self.symbols[0].len())
-        } else {
-            (0, 0)
-        }
+        self.symbols.dimensions()
     }
 
     /// Returns `true` if (x, y) is in bounds of `self.symbols`
     pub fn in_bounds(&self, x: i32, y: i32) -> bool {
-        if self.symbols.len() > 0 {
-            let width = conversions::as_i32(self.symbols.len());
-            let height = conversions::as_i32(self.symbols[0].len());
+        self.symbols.in_bounds(x, y)
+    }
 
-            (x >= 0 && x < width) && (y >= 0 && y < height)
-        } else {
-            false
-        }
+    /// Returns the `CanvasSymbol` at (x, y), or `None` if (x, y) is out of bounds
+    pub fn get(&self, x: i32, y: i32) -> Option<&CanvasSymbol> {
+        self.symbols.get(x, y)
+    }
+
+    /// Returns a mutable reference to the `CanvasSymbol` at (x, y), or `None` if (x, y) is out of bounds
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut CanvasSymbol> {
+        self.symbols.get_mut(x, y)
+    }
+
+    /// Iterates over each row of symbols from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[CanvasSymbol]> {
+        self.symbols.rows()
+    }
+
+    /// Iterates over the symbols in the `width` x `height` rectangle with its top-left at (x, y), as
+    /// `(x, y, symbol)`
+    pub fn rect(&self, x: i32, y: i32, width: usize, height: usize) -> impl Iterator<Item = (usize, usize, &CanvasSymbol)> {
+        self.symbols.rect(x, y, width, height)
+    }
+
+    /// Iterates over the in bounds orthogonal neighbours of (x, y), as `(x, y, symbol)`
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32, &CanvasSymbol)> {
+        self.symbols.neighbours(x, y)
     }
 
-    /// Sets the `CanvasSymbol` at the location specified by `vec2`
+    /// Sets the `CanvasSymbol` at the location specified by `vec2`. Does nothing if `vec2` is out of bounds
     pub fn set_symbol(&mut self, vec2: Vector2<usize>, symbol: CanvasSymbol) {
-        self.symbols[vec2[0]][vec2[1]] = symbol;
+        if let Some(existing) = self.symbol_at(vec2) {
+            *existing = symbol;
+        }
     }
 
     /// Changes the character at the location specified by `vec2`
     pub fn set_character(&mut self, vec2: Vector2<usize>, character: char) {
-        self.symbols[vec2[0]][vec2[1]].character = character;
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.character = character;
+        }
     }
 
     /// Adds a modifier to the location specified by `vec2`
     pub fn add_modifier(&mut self, vec2: Vector2<usize>, modifier: Modifier) {
-        self.symbols[vec2[0]][vec2[1]].modifiers.push(modifier)
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.modifiers.push(modifier);
+        }
     }
 
     /// Removes all modifiers to the location specified by `vec2`
     pub fn clear_modifiers(&mut self, vec2: Vector2<usize>) {
-        self.symbols[vec2[0]][vec2[1]].modifiers = vec!();
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.modifiers.clear();
+        }
     }
 
     /// Changes the foreground color of the symbol at the location specified by `vec2`
     pub fn set_fg_color(&mut self, vec2: Vector2<usize>, color: Color) {
-        self.symbols[vec2[0]][vec2[1]].foreground = color;
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.foreground = color;
+        }
     }
 
     /// Changes the background color of the symbol at the location specified by `vec2`
     pub fn set_bg_color(&mut self, vec2: Vector2<usize>, color: Color) {
-        self.symbols[vec2[0]][vec2[1]].background = color;
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.background = color;
+        }
     }
 
     /// Alters the foreground color by applying `color` with an alpha value
     pub fn apply_fg_color(&mut self, vec2: Vector2<usize>, color: Color, alpha: f64) {
-        let base_color = self.symbols[vec2[0]][vec2[1]].foreground;
-        self.symbols[vec2[0]][vec2[1]].foreground = Self::apply_color(base_color, color, alpha);
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.foreground = Self::apply_color(symbol.foreground, color, alpha);
+        }
     }
 
     /// Alters the background color by applying `color` with an alpha value
     pub fn apply_bg_color(&mut self, vec2: Vector2<usize>, color: Color, alpha: f64) {
-        let base_color = self.symbols[vec2[0]][vec2[1]].background;
-        self.symbols[vec2[0]][vec2[1]].background = Self::apply_color(base_color, color, alpha);
+        if let Some(symbol) = self.symbol_at(vec2) {
+            symbol.background = Self::apply_color(symbol.background, color, alpha);
+        }
+    }
+
+    /// Returns the symbol at `vec2`, or `None` if out of bounds
+    fn symbol_at(&mut self, vec2: Vector2<usize>) -> Option<&mut CanvasSymbol> {
+        self.symbols.get_mut(conversions::as_i32(vec2[0]), conversions::as_i32(vec2[1]))
     }
 
     fn apply_color(base_color: Color, color: Color, alpha: f64) -> Color {
@@ -127,9 +173,9 @@ impl TextCanvas {
             let new_g = clamp(g + ((g_a - g) * alpha), 0.0, 255.0) as u8;
             let new_b = clamp(b + ((b_a - b) * alpha), 0.0, 255.0) as u8;
 
-            return Color::Rgb(new_r, new_g, new_b);
+            Color::Rgb(new_r, new_g, new_b)
         } else {
-            return base_color;
+            base_color
         }
     }
 
@@ -145,13 +191,16 @@ pub struct CanvasSymbol {
 
 impl CanvasSymbol {
 
-    fn span(&self) -> Span {
-        let style = Style::default().fg(self.foreground).bg(self.background);
+    /// Returns `character` styled as the symbol as a `Span`. All of the symbol's modifiers are combined, so modifiers
+    /// added by different sources (the entity, the tile, animations) are all shown
+    fn span(&self, character: char, color_depth: ColorDepth) -> Span<'_> {
+        let (foreground, background) = color_util::quantize_pair(self.foreground, self.background, color_depth);
+        let mut style = Style::default().fg(foreground).bg(background);
         for modifier in &self.modifiers {
-            style.add_modifier(*modifier);
+            style = style.add_modifier(*modifier);
         }
 
-        Span::styled(self.character.to_string(), style)
+        Span::styled(character.to_string(), style)
     }
 
 }
@@ -169,3 +218,38 @@ impl Default for CanvasSymbol {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_span_has_all_modifiers() {
+        let symbol = CanvasSymbol {
+            modifiers: vec!(Modifier::BOLD, Modifier::REVERSED),
+            ..CanvasSymbol::default()
+        };
+
+        let style = symbol.span(symbol.character, ColorDepth::TrueColor).style;
+        assert!(style.add_modifier.contains(Modifier::BOLD | Modifier::REVERSED));
+    }
+
+    /// Returns the text of each line of `canvas`, without the trailing newline
+    fn lines(canvas: &TextCanvas, wide_glyphs: bool) -> Vec<String> {
+        canvas.as_styled_text(ColorDepth::TrueColor, wide_glyphs).lines.iter()
+            .map(|line| line.0.iter().map(|span| span.content.as_ref()).collect::<String>().trim_end_matches('\n').to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_wide_glyphs() {
+        let mut canvas = TextCanvas::with_size(4, 1);
+        canvas.set_character(Vector2::new(0, 0), '🐉');
+        canvas.set_character(Vector2::new(1, 0), 'a');
+        canvas.set_character(Vector2::new(3, 0), '竜');
+
+        // The dragon covers the cell after it, and the last glyph has no room
+        assert_eq!(lines(&canvas, true), vec!("🐉 ?"));
+        assert_eq!(lines(&canvas, false), vec!("?a ?"));
+    }
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+/// Timings of the last frame, shown by the stats overlay to spot slowdowns while playing
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Time since the frame before started
+    pub frame_time: Duration,
+    /// Time from the last input being read to the frame showing it being drawn
+    pub input_latency: Option<Duration>,
+    /// How long each system of the screen on top took to run, by name
+    pub system_times: Vec<(&'static str, Duration)>,
+    /// Number of entities in the world of the screen on top, if it has one
+    pub entity_count: Option<usize>,
+}
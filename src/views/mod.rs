@@ -3,13 +3,21 @@ use tui::backend::CrosstermBackend;
 use tui::Frame as TuiFrame;
 
 pub mod screens;
+pub mod popups;
 
 mod screen;
 mod popup;
+mod frame_stats;
 mod screen_manager;
+mod terminal_guard;
 
 pub use screen::Screen;
 pub use popup::Popup;
+pub use frame_stats::FrameStats;
 pub use screen_manager::ScreenManager;
+pub use terminal_guard::TerminalGuard;
 
-type Frame<'a> = TuiFrame<'a, CrosstermBackend<Stdout>>;
+/// Backend used when drawing to the terminal
+pub type TerminalBackend = CrosstermBackend<Stdout>;
+
+type Frame<'a, B = TerminalBackend> = TuiFrame<'a, B>;
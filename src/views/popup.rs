@@ -1,28 +1,35 @@
 use std::collections::VecDeque;
-use crate::game::GameTick;
+use crate::game::{GameTick, InputContext};
 use super::screen_manager::BoxedCallback;
 use tui::layout::Rect;
-use super::Frame;
+use super::{Frame, TerminalBackend};
+use tui::backend::Backend;
 
 /// Defines a popup view that is showed on top existing displays
-pub trait Popup {
+/// Like `Screen`, is generic over the `Backend` it draws with
+pub trait Popup<B: Backend = TerminalBackend> {
 
     // Instantiates new popup instance
     fn new() -> Self where Self:Sized;
 
     /// Renders the screen onto the terminal
-    fn render(&mut self, frame: &mut Frame, tick: GameTick);
+    fn render(&mut self, frame: &mut Frame<B>, tick: GameTick);
 
     /// Returns `Rect` representing where on screen it'll draw its contents
     fn draw_location(&self) -> Rect;
 
+    /// Returns the set of key bindings used while this popup is on top. Popups are menus unless they say otherwise
+    fn input_context(&self) -> InputContext {
+        InputContext::Menu
+    }
+
     /// Performs clean up when screen is dropped
     fn tear_down(&mut self) {}
 
     /// Add a `ScreenManager` function to be called after next loop
-    fn add_screen_manager_callback(&mut self, callback: BoxedCallback);
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>);
 
     /// Get all queued `ScreenManager` functions to be called
-    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback>;
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>>;
 
 }
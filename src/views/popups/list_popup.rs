@@ -0,0 +1,207 @@
+use super::Frame;
+use crate::views::{Popup, ScreenManager, TerminalBackend};
+use crate::views::screen_manager::BoxedCallback;
+use crate::game::{Command, GameTick, MouseAction, MouseInput};
+use crate::utility::conversions;
+use crate::config::theme::RoleColors;
+use std::collections::VecDeque;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// Lines moved by each step of the mouse wheel
+const SCROLL_LINES: usize = 3;
+
+/// Popup showing a list of items, where one is highlighted. The highlight is moved with the keys, and the list
+/// can be scrolled with the mouse wheel
+pub struct ListPopup<B: Backend = TerminalBackend> {
+    title: String,
+    items: Vec<String>,
+    /// Index in `items` of the highlighted item
+    selected: usize,
+    /// Index in `items` of the first item shown
+    offset: usize,
+    /// Style the highlighted item is drawn with
+    highlight: Style,
+    area: Rect,
+    callbacks: VecDeque<BoxedCallback<B>>,
+}
+
+impl<B: Backend> Popup<B> for ListPopup<B> {
+
+    fn new() -> Self {
+        ListPopup::with_items("", vec!(), Rect::new(0, 0, 30, 10))
+    }
+
+    fn render(&mut self, frame: &mut Frame<B>, tick: GameTick) {
+        match tick {
+            GameTick::Command(_, command) => self.handle_command(command),
+            GameTick::Mouse(_, MouseInput { action: MouseAction::ScrollUp, .. }) => self.scroll_up(SCROLL_LINES),
+            GameTick::Mouse(_, MouseInput { action: MouseAction::ScrollDown, .. }) => self.scroll_down(SCROLL_LINES),
+            _ => ()
+        }
+
+        let lines: Vec<Spans> = self.items.iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(self.visible_rows())
+            .map(|(i, item)| {
+                let style = if i == self.selected {
+                    self.highlight
+                } else {
+                    Style::default()
+                };
+                Spans::from(Span::styled(item.clone(), style))
+            })
+            .collect();
+
+        let list = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(self.title.as_str())
+                    .borders(Borders::ALL)
+            );
+
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(list, self.area);
+    }
+
+    fn draw_location(&self) -> Rect {
+        self.area
+    }
+
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>) {
+        self.callbacks.push_front(callback)
+    }
+
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>> {
+        self.callbacks.drain(0..).collect()
+    }
+
+}
+
+impl<B: Backend> ListPopup<B> {
+
+    /// Creates `ListPopup` titled `title` listing `items`, drawn in `area`
+    pub fn with_items(title: &str, items: Vec<String>, area: Rect) -> Self {
+        ListPopup {
+            title: title.to_string(),
+            items,
+            selected: 0,
+            offset: 0,
+            highlight: Style::default().add_modifier(Modifier::REVERSED),
+            area,
+            callbacks: VecDeque::new(),
+        }
+    }
+
+    /// Draws the highlighted item with `colors` instead of in reversed video
+    pub fn with_highlight(mut self, colors: RoleColors) -> Self {
+        self.highlight = Style::default().fg(colors.foreground).bg(colors.background);
+        self
+    }
+
+    /// Returns the highlighted item, or `None` if the list is empty
+    pub fn selected(&self) -> Option<&String> {
+        self.items.get(self.selected)
+    }
+
+    fn handle_command(&mut self, command: Command) {
+        let page = self.visible_rows().max(1);
+
+        match command {
+            Command::Up => self.select(self.selected.saturating_sub(1)),
+            Command::Down => self.select(self.selected + 1),
+            Command::PreviousPage => self.select(self.selected.saturating_sub(page)),
+            Command::NextPage => self.select(self.selected + page),
+            Command::Confirm | Command::Cancel => {
+                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<B>| {
+                    s.pop_popup();
+                }));
+            },
+            _ => ()
+        }
+    }
+
+    /// Highlights the item at `index`, or the last item if `index` is past the end, and scrolls so it can be seen
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+
+        let rows = self.visible_rows().max(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+    }
+
+    /// Moves the list up by `lines`, keeping the highlighted item on screen
+    fn scroll_up(&mut self, lines: usize) {
+        self.offset = self.offset.saturating_sub(lines);
+        self.keep_selected_in_view();
+    }
+
+    /// Moves the list down by `lines`, stopping when the last item is at the bottom, and keeping the highlighted item
+    /// on screen
+    fn scroll_down(&mut self, lines: usize) {
+        let last_offset = self.items.len().saturating_sub(self.visible_rows());
+        self.offset = (self.offset + lines).min(last_offset);
+        self.keep_selected_in_view();
+    }
+
+    fn keep_selected_in_view(&mut self) {
+        let last_visible = (self.offset + self.visible_rows()).saturating_sub(1);
+        self.selected = self.selected.clamp(self.offset, last_visible.max(self.offset));
+    }
+
+    /// Returns how many items fit inside the border
+    fn visible_rows(&self) -> usize {
+        conversions::u16_to_usize(self.area.height.saturating_sub(2))
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tui::backend::TestBackend;
+
+    /// List of 20 numbered items that shows 5 at a time
+    fn numbered_list() -> ListPopup<TestBackend> {
+        let items = (0..20).map(|i| i.to_string()).collect();
+        ListPopup::with_items("numbers", items, Rect::new(0, 0, 10, 7))
+    }
+
+    #[test]
+    fn test_select_scrolls_into_view() {
+        let mut list = numbered_list();
+
+        list.handle_command(Command::NextPage);
+        list.handle_command(Command::Down);
+        assert_eq!(list.selected(), Some(&"6".to_string()));
+        assert_eq!(list.offset, 2);
+
+        list.select(100);
+        assert_eq!(list.selected(), Some(&"19".to_string()));
+        assert_eq!(list.offset, 15);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut list = numbered_list();
+
+        list.scroll_down(3);
+        assert_eq!(list.offset, 3);
+        // Highlight is dragged along so it stays on screen
+        assert_eq!(list.selected, 3);
+
+        list.scroll_down(100);
+        assert_eq!(list.offset, 15);
+
+        list.scroll_up(SCROLL_LINES);
+        assert_eq!(list.offset, 12);
+        assert_eq!(list.selected, 15);
+    }
+}
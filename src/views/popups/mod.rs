@@ -0,0 +1,5 @@
+use super::Frame;
+
+pub mod list_popup;
+pub mod text_input_popup;
+pub mod stats_popup;
@@ -0,0 +1,128 @@
+use super::Frame;
+use crate::views::{FrameStats, Popup, TerminalBackend};
+use crate::views::screen_manager::BoxedCallback;
+use crate::game::GameTick;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+use tui::text::Spans;
+
+/// Width of the overlay, including its border
+const WIDTH: u16 = 38;
+
+/// Overlay in the top left corner showing `FrameStats`: frame time, input latency, entity count and the time each
+/// system took. Only shows what it's given, and never gets input
+pub struct StatsPopup<B: Backend = TerminalBackend> {
+    stats: FrameStats,
+    area: Rect,
+    callbacks: VecDeque<BoxedCallback<B>>,
+}
+
+impl<B: Backend> Popup<B> for StatsPopup<B> {
+
+    fn new() -> Self {
+        StatsPopup {
+            stats: FrameStats::default(),
+            area: Rect::default(),
+            callbacks: VecDeque::new(),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame<B>, _: GameTick) {
+        let lines = self.lines();
+
+        let size = frame.size();
+        self.area = Rect::new(size.x, size.y, WIDTH.min(size.width), (lines.len() as u16 + 2).min(size.height));
+
+        let stats = Paragraph::new(lines.into_iter().map(Spans::from).collect::<Vec<Spans>>())
+            .block(
+                Block::default()
+                    .title("Stats")
+                    .borders(Borders::ALL)
+            );
+
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(stats, self.area);
+    }
+
+    fn draw_location(&self) -> Rect {
+        self.area
+    }
+
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>) {
+        self.callbacks.push_front(callback)
+    }
+
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>> {
+        self.callbacks.drain(0..).collect()
+    }
+
+}
+
+impl<B: Backend> StatsPopup<B> {
+
+    /// Changes the stats shown from the next render
+    pub fn set_stats(&mut self, stats: FrameStats) {
+        self.stats = stats;
+    }
+
+    /// Returns a line for each stat
+    fn lines(&self) -> Vec<String> {
+        let stats = &self.stats;
+        let fps = match stats.frame_time.as_secs_f64() {
+            seconds if seconds > 0.0 => format!(" ({:.0} fps)", 1.0 / seconds),
+            _ => String::new()
+        };
+
+        let mut lines = vec!(
+            format!("{:<18}{}{}", "Frame", millis(stats.frame_time), fps),
+            match stats.input_latency {
+                Some(latency) => format!("{:<18}{}", "Input latency", millis(latency)),
+                None => format!("{:<18}-", "Input latency")
+            },
+        );
+
+        if let Some(count) = stats.entity_count {
+            lines.push(format!("{:<18}{}", "Entities", count));
+        }
+
+        for &(name, time) in &stats.system_times {
+            lines.push(format!("{:<18}{}", name, millis(time)));
+        }
+
+        lines
+    }
+
+}
+
+/// Formats `time` in milliseconds, like `"1.25 ms"`
+fn millis(time: Duration) -> String {
+    format!("{:.2} ms", time.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tui::backend::TestBackend;
+
+    #[test]
+    fn test_lines() {
+        let mut popup: StatsPopup<TestBackend> = StatsPopup::new();
+        assert_eq!(popup.lines(), vec!("Frame             0.00 ms", "Input latency     -"));
+
+        popup.set_stats(FrameStats {
+            frame_time: Duration::from_millis(20),
+            input_latency: Some(Duration::from_micros(1500)),
+            system_times: vec!(("Command", Duration::from_micros(250))),
+            entity_count: Some(12),
+        });
+        assert_eq!(popup.lines(), vec!(
+            "Frame             20.00 ms (50 fps)",
+            "Input latency     1.50 ms",
+            "Entities          12",
+            "Command           0.25 ms",
+        ));
+    }
+}
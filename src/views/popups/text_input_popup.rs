@@ -0,0 +1,253 @@
+use super::Frame;
+use crate::views::{Popup, ScreenManager, TerminalBackend};
+use crate::views::screen_manager::BoxedCallback;
+use crate::game::{Command, GameTick, InputContext};
+use crate::utility::{conversions, glyph};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::VecDeque;
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// Called with the text typed into a `TextInputPopup` once it's confirmed
+pub type SubmitCallback<B = TerminalBackend> = Box<dyn FnMut(&mut ScreenManager<B>, String)>;
+
+/// Popup with a line of text to type into, for things like naming a character. Confirming closes the popup and hands
+/// the text to the `SubmitCallback` it was made with. Cancelling closes it without calling it
+pub struct TextInputPopup<B: Backend = TerminalBackend> {
+    title: String,
+    text: String,
+    /// Number of characters before the cursor
+    cursor: usize,
+    area: Rect,
+    on_submit: Option<SubmitCallback<B>>,
+    callbacks: VecDeque<BoxedCallback<B>>,
+}
+
+impl<B: Backend + 'static> Popup<B> for TextInputPopup<B> {
+
+    fn new() -> Self {
+        TextInputPopup::with_prompt("", Rect::new(0, 0, 30, 3), Box::new(|_, _| ()))
+    }
+
+    fn render(&mut self, frame: &mut Frame<B>, tick: GameTick) {
+        match tick {
+            GameTick::Key(_, key) => self.handle_key(key),
+            GameTick::Command(_, Command::Confirm) => self.submit(),
+            GameTick::Command(_, Command::Cancel) => {
+                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<B>| {
+                    s.pop_popup();
+                }));
+            },
+            _ => ()
+        }
+
+        // Scroll so the cursor stays inside the border
+        let width = conversions::u16_to_usize(self.area.width.saturating_sub(2));
+        let offset = self.scroll_offset(width);
+        let mut columns = 0;
+        let shown: String = self.text.chars()
+            .skip(offset)
+            .take_while(|&character| {
+                columns += glyph::width(character);
+                columns <= width
+            })
+            .collect();
+        let cursor_column = self.columns(offset, self.cursor);
+
+        let input = Paragraph::new(shown)
+            .block(
+                Block::default()
+                    .title(self.title.as_str())
+                    .borders(Borders::ALL)
+            );
+
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(input, self.area);
+        frame.set_cursor(self.area.x + 1 + cursor_column as u16, self.area.y + 1);
+    }
+
+    fn draw_location(&self) -> Rect {
+        self.area
+    }
+
+    fn input_context(&self) -> InputContext {
+        InputContext::Text
+    }
+
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>) {
+        self.callbacks.push_front(callback)
+    }
+
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>> {
+        self.callbacks.drain(0..).collect()
+    }
+
+}
+
+impl<B: Backend + 'static> TextInputPopup<B> {
+
+    /// Creates an empty `TextInputPopup` titled `title`, drawn in `area`, that calls `on_submit` with the text once
+    /// it's confirmed
+    pub fn with_prompt(title: &str, area: Rect, on_submit: SubmitCallback<B>) -> Self {
+        TextInputPopup {
+            title: title.to_string(),
+            text: String::new(),
+            cursor: 0,
+            area,
+            on_submit: Some(on_submit),
+            callbacks: VecDeque::new(),
+        }
+    }
+
+    /// Returns the text typed so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Types the character of `key` at the cursor, or edits the text or moves the cursor for keys like backspace
+    fn handle_key(&mut self, key: KeyEvent) {
+        let length = self.text.chars().count();
+
+        match key.code {
+            KeyCode::Char(character) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                let index = self.byte_index(self.cursor);
+                self.text.insert(index, character);
+                self.cursor += 1;
+            },
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+            },
+            KeyCode::Delete if self.cursor < length => {
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+            },
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(length),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = length,
+            _ => ()
+        }
+    }
+
+    /// Closes the popup, then hands the text to the submit callback
+    fn submit(&mut self) {
+        let text = self.text.clone();
+        let mut on_submit = self.on_submit.take();
+
+        self.add_screen_manager_callback(Box::new(move |s: &mut ScreenManager<B>| {
+            s.pop_popup();
+            if let Some(on_submit) = on_submit.as_mut() {
+                on_submit(s, text.clone());
+            }
+        }));
+    }
+
+    /// Returns how many characters to scroll past so the cursor, and the cell it's drawn in, fit in `width` columns
+    fn scroll_offset(&self, width: usize) -> usize {
+        (0..self.cursor)
+            .find(|&offset| self.columns(offset, self.cursor) < width)
+            .unwrap_or(self.cursor)
+    }
+
+    /// Returns how many terminal columns the characters from `start` up to `end` take up, as wide glyphs take two
+    fn columns(&self, start: usize, end: usize) -> usize {
+        self.text.chars().skip(start).take(end - start).map(glyph::width).sum()
+    }
+
+    /// Returns the index in bytes of the character `cursor` characters into the text
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text.char_indices()
+            .nth(cursor)
+            .map(|(index, _)| index)
+            .unwrap_or_else(|| self.text.len())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utility::test_util::TestResult;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+    use tui::Terminal;
+    use tui::backend::TestBackend;
+
+    fn type_keys(popup: &mut TextInputPopup<TestBackend>, codes: &[KeyCode]) {
+        for &code in codes {
+            popup.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut popup: TextInputPopup<TestBackend> = TextInputPopup::new();
+
+        type_keys(&mut popup, &[KeyCode::Char('r'), KeyCode::Char('ö'), KeyCode::Char('g'), KeyCode::Char('e')]);
+        assert_eq!(popup.text(), "röge");
+
+        type_keys(&mut popup, &[KeyCode::Left, KeyCode::Left, KeyCode::Backspace, KeyCode::Char('o')]);
+        assert_eq!(popup.text(), "roge");
+
+        type_keys(&mut popup, &[KeyCode::Home, KeyCode::Delete, KeyCode::Char('R'), KeyCode::End, KeyCode::Char('!')]);
+        assert_eq!(popup.text(), "Roge!");
+
+        // Nothing to delete past either end
+        type_keys(&mut popup, &[KeyCode::Delete, KeyCode::Home, KeyCode::Backspace]);
+        assert_eq!(popup.text(), "Roge!");
+    }
+
+    #[test]
+    fn test_wide_glyphs_scroll() -> TestResult {
+        let mut terminal = Terminal::new(TestBackend::new(10, 3))?;
+        let mut popup: TextInputPopup<TestBackend> = TextInputPopup::with_prompt("", Rect::new(0, 0, 6, 3), Box::new(|_, _| ()));
+        let tick = GameTick::Tick(Duration::from_millis(16));
+
+        // Each glyph takes two of the four columns inside the border
+        type_keys(&mut popup, &[KeyCode::Char('日')]);
+        terminal.draw(|f| popup.render(f, tick))?;
+        assert_eq!(terminal.get_cursor()?, (3, 1));
+
+        // The cursor needs a column after the text, so the first glyph scrolls out of view
+        type_keys(&mut popup, &[KeyCode::Char('本')]);
+        terminal.draw(|f| popup.render(f, tick))?;
+        assert_eq!(terminal.get_cursor()?, (3, 1));
+        assert_eq!(terminal.backend().buffer().get(1, 1).symbol, "本");
+
+        type_keys(&mut popup, &[KeyCode::Home]);
+        terminal.draw(|f| popup.render(f, tick))?;
+        assert_eq!(terminal.get_cursor()?, (1, 1));
+        assert_eq!(terminal.backend().buffer().get(1, 1).symbol, "日");
+        assert_eq!(terminal.backend().buffer().get(3, 1).symbol, "本");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_submit() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(40, 10, vec!())?;
+        let submitted = Rc::new(RefCell::new(None));
+
+        let receiver = Rc::clone(&submitted);
+        screen_manager.push_popup(TextInputPopup::with_prompt("Name", Rect::new(0, 0, 20, 3), Box::new(move |_, text| {
+            *receiver.borrow_mut() = Some(text);
+        })));
+        assert_eq!(screen_manager.input_context(), InputContext::Text);
+
+        let deltatime = Duration::from_millis(16);
+        for character in "Al".chars() {
+            screen_manager.step(GameTick::Key(deltatime, KeyEvent::new(KeyCode::Char(character), KeyModifiers::NONE)))?;
+        }
+        assert_eq!(*submitted.borrow(), None);
+
+        screen_manager.step(GameTick::Command(deltatime, Command::Confirm))?;
+        assert_eq!(submitted.borrow().as_deref(), Some("Al"));
+        assert_eq!(screen_manager.input_context(), InputContext::default());
+
+        Ok(())
+    }
+}
@@ -1,25 +1,53 @@
 use std::collections::VecDeque;
-use crate::game::GameTick;
+use crate::game::{GameTick, InputContext};
+use crate::game::save::SaveError;
 use super::screen_manager::BoxedCallback;
-use super::Frame;
+use super::{Frame, FrameStats, TerminalBackend};
+use tui::backend::Backend;
 
 
 /// Defines a primary view, that shows various widgets
-pub trait Screen {
+///
+/// Is generic over the `Backend` it draws with, so it can be drawn without a terminal, such as in tests
+pub trait Screen<B: Backend = TerminalBackend> {
 
     /// Instantiates new instance of this screen
     fn new() -> Self where Self:Sized;
 
     /// Renders the screen onto the terminal
-    fn render(&mut self, frame: &mut Frame, tick: GameTick);
+    fn render(&mut self, frame: &mut Frame<B>, tick: GameTick);
+
+    /// Returns the set of key bindings used while this screen is on top
+    fn input_context(&self) -> InputContext {
+        InputContext::Game
+    }
+
+    /// Returns `true` while the screen is still carrying out earlier input, such as animating a move
+    fn is_busy(&self) -> bool {
+        false
+    }
+
+    /// Shows `message` to the player, such as an error from outside the screen
+    fn show_message(&mut self, _message: String) {}
+
+    /// Shows what input is going to, such as a macro being recorded, until it's changed. `None` clears it
+    fn show_input_status(&mut self, _status: Option<String>) {}
+
+    /// Adds what the screen knows about the last frame, like how long its systems took, to `stats`
+    fn report_stats(&self, _stats: &mut FrameStats) {}
+
+    /// Saves the screen's progress so it can be carried on later, such as when the game is stopped by a signal
+    fn save(&self) -> Result<(), SaveError> {
+        Ok(())
+    }
 
     /// Performs clean up when screen is dropped
     fn tear_down(&mut self) {}
 
     /// Add a `ScreenManager` function to be called after next loop
-    fn add_screen_manager_callback(&mut self, callback: BoxedCallback);
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>);
 
     /// Get all queued `ScreenManager` functions to be called
-    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback>;
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>>;
 
 }
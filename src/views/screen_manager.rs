@@ -1,72 +1,117 @@
-use crate::game::GameTick;
+use crate::config;
+use crate::game::{Command, GameTick, InputContext};
 use crate::game::input_manager::InputManager;
 use crate::game::input_manager::InputManagerError;
+use crate::game::save::SaveError;
 use crate::game::source::{Source, FakeSource, EventSource};
 use crossterm::event::Event;
 use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt;
-use std::io::Stdout;
 use std::io;
-use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use super::popups::stats_popup::StatsPopup;
 use super::Popup;
 use super::Screen;
-use super::Frame;
-use tui::Terminal as TuiTerminal;
-use tui::backend::CrosstermBackend;
+use super::{Frame, FrameStats, TerminalBackend};
+use tui::Terminal;
+use tui::backend::{Backend, CrosstermBackend, TestBackend};
 
 type Result<T> = std::result::Result<T, ScreenManagerError>;
-type Terminal = TuiTerminal<CrosstermBackend<Stdout>>;
 
-pub type BoxedCallback = Box<dyn FnMut(&mut ScreenManager)>;
+pub type BoxedCallback<B = TerminalBackend> = Box<dyn FnMut(&mut ScreenManager<B>)>;
 
 /// Manages screens and popups in the game, and controls which views get inputs
 /// To properly draw, should enable raw mode on terminal before use (and clear screen)
-pub struct ScreenManager {
-    screens: Vec<Box<dyn Screen>>,
-    popups: Vec<Box<dyn Popup>>,
+///
+/// Draws with `B`, which is the terminal unless made with `headless`
+pub struct ScreenManager<B: Backend = TerminalBackend> {
+    screens: Vec<Box<dyn Screen<B>>>,
+    popups: Vec<Box<dyn Popup<B>>>,
     input_manager: InputManager,
-    terminal: Terminal,
-    callback_queue: VecDeque<BoxedCallback>,
+    terminal: Terminal<B>,
+    callback_queue: VecDeque<BoxedCallback<B>>,
     pub should_quit: bool,
+    /// Set when the process is asked to stop, such as by SIGTERM
+    interrupted: Arc<AtomicBool>,
+    /// Shortest time between frames, or `None` to draw as often as ticks come in
+    frame_interval: Option<Duration>,
+    /// When the last frame started
+    last_frame: Instant,
+    /// Timings of the last frame
+    stats: FrameStats,
+    /// When the earliest input that hasn't been drawn yet was read
+    undrawn_input: Option<Instant>,
+    /// Drawn over everything else while shown. Kept apart from the popups so it never takes input
+    stats_overlay: Option<StatsPopup<B>>,
 }
 
 impl ScreenManager {
 
     /// Creates a new `ScreenManager` that gets input from `stdin`
     pub fn new() -> Result<ScreenManager> {
-        ScreenManager::init(EventSource::new())
+        ScreenManager::init(CrosstermBackend::new(io::stdout()), EventSource::new())
+    }
+
+    /// Creates a new `ScreenManager` that gets input from `source`, such as a `ReplaySource`
+    pub fn with_source(source: impl Source + Send + 'static) -> Result<ScreenManager> {
+        ScreenManager::init(CrosstermBackend::new(io::stdout()), source)
     }
 
     /// Creates a new `ScreenMananger` that uses a `FakeSource` to get input
     pub fn debug_new(events: Vec<Event>) -> Result<ScreenManager> {
-        ScreenManager::init(FakeSource::new(events))
+        ScreenManager::init(CrosstermBackend::new(io::stdout()), FakeSource::new(events))
+    }
+
+}
+
+impl ScreenManager<TestBackend> {
+
+    /// Creates a new `ScreenManager` that draws to a `width` x `height` buffer in memory instead of the terminal, and
+    /// uses a `FakeSource` to get input
+    pub fn headless(width: u16, height: u16, events: Vec<Event>) -> Result<ScreenManager<TestBackend>> {
+        ScreenManager::init(TestBackend::new(width, height), FakeSource::new(events))
     }
 
-    /// Initializes a `ScreenManager` with no screens or popups
-    fn init(source: impl Source + Send + 'static) -> Result<ScreenManager> {
-        let tick_rate = Duration::from_millis(16);
+}
+
+impl<B: Backend> ScreenManager<B> {
+
+    /// Initializes a `ScreenManager` with no screens or popups, using the frame rate and tick rate from the config
+    fn init(backend: B, source: impl Source + Send + 'static) -> Result<ScreenManager<B>> {
+        let tick_rate = config::get().tick_rate;
         let tick_timeout = Duration::from_secs(1);
 
-        let terminal = Self::setup_terminal()?;
+        let terminal = Self::setup_terminal(backend)?;
 
         let screen_manager = ScreenManager {
             screens: Vec::new(),
             popups: Vec::new(),
-            input_manager: InputManager::new(source, tick_rate, tick_timeout),
+            input_manager: InputManager::new(
+                source,
+                config::get().keymap.clone(),
+                config::get().macros.clone(),
+                tick_rate,
+                tick_timeout
+            ),
             terminal,
-            callback_queue: VecDeque::<BoxedCallback>::new(),
-            should_quit: false
+            callback_queue: VecDeque::<BoxedCallback<B>>::new(),
+            should_quit: false,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            frame_interval: frame_interval(config::get().target_fps),
+            last_frame: Instant::now(),
+            stats: FrameStats::default(),
+            undrawn_input: None,
+            stats_overlay: None,
         };
 
         Ok(screen_manager)
     }
 
-    /// Creates terminal using Crossterm Backend for drawing
-    fn setup_terminal() -> Result<Terminal> {
-        let stdout = io::stdout();
-
-        let backend = CrosstermBackend::new(stdout);
+    /// Creates terminal that draws with `backend`
+    fn setup_terminal(backend: B) -> Result<Terminal<B>> {
         let mut terminal = Terminal::new(backend)?;
 
         terminal.clear()?;
@@ -80,27 +125,178 @@ impl ScreenManager {
     ///
     /// Callbacks are called in order of the Popup stack, then the Screen stack, both from top to bottom. As each is
     /// called, it is removed from the queue
+    ///
+    /// Frames are drawn no more often than the target frame rate allows. Ticks in between are still given to the
+    /// screens and popups, but what they draw isn't shown
+    ///
+    /// If the loop is ended by a signal, every screen is saved before returning
     pub fn start_main_loop(&mut self) -> Result<()> {
-        while !self.should_quit {
-            let tick = self.input_manager.tick()?;
-            let screens = &mut self.screens;
-            let popups = &mut self.popups;
+        while !self.should_quit && !self.interrupted.load(Ordering::Relaxed) {
+            let tick = self.next_tick()?;
 
-            self.terminal.draw(move |f| {
-                render(f, screens, popups, tick);
-            })?;
+            if self.is_frame_due() {
+                self.step(tick)?;
+            } else {
+                self.step_without_drawing(tick)?;
+            }
+        }
 
-            self.handle_callbacks();
+        if self.interrupted.load(Ordering::Relaxed) {
+            for screen in self.screens.iter() {
+                screen.save()?;
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the next tick of input, noting when its input was read if the last frame already showed earlier input
+    fn next_tick(&mut self) -> Result<GameTick> {
+        let tick = self.input_manager.tick(self.input_context(), self.is_busy())?;
+        self.update_input_status();
+
+        if self.undrawn_input.is_none() {
+            self.undrawn_input = self.input_manager.last_input_read();
+        }
+
+        Ok(tick)
+    }
+
+    /// Passes messages from input handling, and what's being recorded, to the screen on top
+    fn update_input_status(&mut self) {
+        let message = self.input_manager.take_message();
+
+        if let Some(screen) = self.screens.last_mut() {
+            if let Some(message) = message {
+                screen.show_message(message);
+            }
+            screen.show_input_status(self.input_manager.macro_status());
+        }
+    }
+
+    /// Returns `true` if the frame interval has passed since the last frame started
+    fn is_frame_due(&self) -> bool {
+        self.frame_interval.is_none_or(|interval| self.last_frame.elapsed() >= interval)
+    }
+
+    /// Makes SIGTERM and SIGINT end the main loop the same way quitting does, after saving the screens, so screens are
+    /// torn down and the terminal is put back, rather than the process being killed
+    pub fn quit_on_signals(&self) -> io::Result<()> {
+        for &signal in [signal_hook::SIGTERM, signal_hook::SIGINT].iter() {
+            signal_hook::flag::register(signal, Arc::clone(&self.interrupted))?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws one frame with `tick`, then calls any added callbacks. `Command::ToggleStats` shows or hides the stats
+    /// overlay instead of going to the screens and popups
+    pub fn step(&mut self, tick: GameTick) -> Result<()> {
+        let tick = self.handle_toggle_stats(tick);
+
+        let now = Instant::now();
+        self.stats.frame_time = now - self.last_frame;
+        self.last_frame = now;
+
+        let screens = &mut self.screens;
+        let popups = &mut self.popups;
+        let stats = &mut self.stats;
+        let stats_overlay = &mut self.stats_overlay;
+        let undrawn_input = self.undrawn_input.take();
+
+        self.terminal.draw(move |f| {
+            render(f, screens, popups, tick);
+
+            if let Some(read_at) = undrawn_input {
+                stats.input_latency = Some(read_at.elapsed());
+            }
+
+            if let Some(overlay) = stats_overlay {
+                stats.system_times.clear();
+                stats.entity_count = None;
+                if let Some(screen) = screens.last() {
+                    screen.report_stats(stats);
+                }
+
+                overlay.set_stats(stats.clone());
+                overlay.render(f, remove_input_from_tick(tick));
+            }
+        })?;
+
+        self.handle_callbacks();
+
+        Ok(())
+    }
+
+    /// Gives `tick` to the screens and popups like `step`, then calls any added callbacks, but throws away what was
+    /// drawn instead of showing it
+    fn step_without_drawing(&mut self, tick: GameTick) -> Result<()> {
+        let tick = self.handle_toggle_stats(tick);
+
+        let mut frame = self.terminal.get_frame();
+        render(&mut frame, &mut self.screens, &mut self.popups, tick);
+        self.terminal.current_buffer_mut().reset();
+
+        self.handle_callbacks();
+
+        Ok(())
+    }
+
+    /// Shows or hides the stats overlay if `tick` is `Command::ToggleStats`, returning the tick the screens and
+    /// popups should get instead
+    fn handle_toggle_stats(&mut self, tick: GameTick) -> GameTick {
+        match tick {
+            GameTick::Command(deltatime, Command::ToggleStats) => {
+                self.toggle_stats();
+                GameTick::Tick(deltatime)
+            },
+            tick => tick
+        }
+    }
+
+    /// Returns the input context of the view that gets commands: the topmost popup, or if there are no popups, the
+    /// topmost screen
+    pub fn input_context(&self) -> InputContext {
+        match (self.popups.last(), self.screens.last()) {
+            (Some(popup), _) => popup.input_context(),
+            (None, Some(screen)) => screen.input_context(),
+            (None, None) => InputContext::default()
+        }
+    }
+
+    /// Returns `true` if the topmost screen is still carrying out earlier input, so macros wait before their next step
+    pub fn is_busy(&self) -> bool {
+        self.screens.last().is_some_and(|screen| screen.is_busy())
+    }
+
+    /// Shows the stats overlay if it's hidden, or hides it if it's shown
+    pub fn toggle_stats(&mut self) {
+        self.stats_overlay = match self.stats_overlay {
+            Some(_) => None,
+            None => Some(StatsPopup::new())
+        };
+    }
+
+    /// Returns `true` if the stats overlay is shown
+    pub fn is_showing_stats(&self) -> bool {
+        self.stats_overlay.is_some()
+    }
+
+    /// Stops the macro that's playing, if there is one
+    pub fn stop_macro(&mut self) {
+        self.input_manager.stop_macro();
+    }
+
+    /// Returns the backend that is drawn to
+    pub fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
     /// Updates the queue using the callbacks of the screens and popups, and calls each in turn
     fn handle_callbacks(&mut self) {
         self.update_callback_queue();
 
-        let callbacks: Vec<BoxedCallback> = self.callback_queue.drain(0..).collect();
+        let callbacks: Vec<BoxedCallback<B>> = self.callback_queue.drain(0..).collect();
         for mut callback in callbacks {
             callback(self);
         }
@@ -121,28 +317,28 @@ impl ScreenManager {
     }
 
     /// Push a new `Screen` to the top of the Screen stack
-    pub fn push_screen(&mut self, screen: impl Screen + 'static) {
+    pub fn push_screen(&mut self, screen: impl Screen<B> + 'static) {
         self.screens.push(Box::new(screen));
     }
 
     /// Pops a `Screen` from the Screen stack
-    pub fn pop_screen(&mut self) -> Option<Box<dyn Screen>> {
+    pub fn pop_screen(&mut self) -> Option<Box<dyn Screen<B>>> {
         self.screens.pop()
     }
 
     /// Push a new `Popup` to the top of the Popup stack
-    pub fn push_popup(&mut self, popup: impl Popup + 'static) {
+    pub fn push_popup(&mut self, popup: impl Popup<B> + 'static) {
         self.popups.push(Box::new(popup));
     }
 
     /// Pops a `Popup` from the Popup stack
-    pub fn pop_popup(&mut self) -> Option<Box<dyn Popup>> {
+    pub fn pop_popup(&mut self) -> Option<Box<dyn Popup<B>>> {
         self.popups.pop()
     }
 
 }
 
-impl Drop for ScreenManager {
+impl<B: Backend> Drop for ScreenManager<B> {
 
     /// Calls tear_down on all screens and popups before they are dropped
     fn drop(&mut self) {
@@ -157,19 +353,34 @@ impl Drop for ScreenManager {
 
 }
 
+/// Returns the shortest time between frames to draw `target_fps` frames a second, or `None` for no limit
+fn frame_interval(target_fps: u32) -> Option<Duration> {
+    match target_fps {
+        0 => None,
+        fps => Some(Duration::from_secs(1) / fps)
+    }
+}
+
 /// Renders the screen (popups and screen stack)
 ///
-/// If there are both popups and screens, then if the `tick` was a Command, the topmost Popup will receive
-/// the Command, while the rest of the popups just receive the deltatime Tick.
+/// If there are both popups and screens, then if the `tick` was a Command or mouse input, the topmost Popup will
+/// receive it, while the rest of the popups just receive the deltatime Tick.
 /// Then the topmost screen will also receive the deltatime Tick, while the rest of the screens don't receive
 /// anything
-fn render(f: &mut Frame, screens: &mut Vec<Box<dyn Screen>>, popups: &mut Vec<Box<dyn Popup>>, tick: GameTick) {
-    let tick = render_popups(f, popups, tick);
-    render_screens(f, screens, tick);
+fn render<B: Backend>(
+    f: &mut Frame<B>,
+    screens: &mut Vec<Box<dyn Screen<B>>>,
+    popups: &mut Vec<Box<dyn Popup<B>>>,
+    tick: GameTick
+) {
+    // Popups are drawn over the screens, but the top popup still gets the input rather than the screen under it
+    let screen_tick = if popups.is_empty() { tick } else { remove_input_from_tick(tick) };
+    render_screens(f, screens, screen_tick);
+    render_popups(f, popups, tick);
 }
 
 /// Renders the topmost screen in the screen stack (not giving any other screens the deltatime Tick)
-fn render_screens(f: &mut Frame, screens: &mut Vec<Box<dyn Screen>>, tick: GameTick) {
+fn render_screens<B: Backend>(f: &mut Frame<B>, screens: &mut Vec<Box<dyn Screen<B>>>, tick: GameTick) {
     if let Some(top_screen) = screens.last_mut() {
         top_screen.render(f, tick);
     }
@@ -177,47 +388,43 @@ fn render_screens(f: &mut Frame, screens: &mut Vec<Box<dyn Screen>>, tick: GameT
 
 /// Renders each of the popups, only allowing the topmost popup to get commands, while giving popups below it just
 /// the deltatime Tick
-fn render_popups(f: &mut Frame, popups: &mut Vec<Box<dyn Popup>>, tick: GameTick) -> GameTick {
-    let mut tick = tick;
-
+fn render_popups<B: Backend>(f: &mut Frame<B>, popups: &mut [Box<dyn Popup<B>>], tick: GameTick) {
     let popups_length = popups.len();
 
-    for i in 0..popups_length {
-        let popup_screen = &mut popups[i];
-
+    for (i, popup_screen) in popups.iter_mut().enumerate() {
         if i == popups_length - 1 {
             // Render topmost popup with the full tick + command
             popup_screen.render(f, tick);
-
-            // Downgrade tick as the command has been "used"
-            tick = remove_input_from_tick(tick);
         } else {
             // render with just deltatime
             let deltatime_tick = remove_input_from_tick(tick);
             popup_screen.render(f, deltatime_tick);
         }
     }
-
-    tick
 }
 
-/// "Downgrades" a `GameTick` from `GameTick::Command` to `GameTick::Tick`, maintaining the deltatime.
+/// "Downgrades" a `GameTick` with input, such as `GameTick::Command`, to `GameTick::Tick`, maintaining the deltatime.
 /// If it is already a `Tick`, just returns a copy
 /// Mainly to give deltatime updates but avoid giving command updates to some screens.
 ///
 fn remove_input_from_tick(tick: GameTick) -> GameTick {
     match tick {
-        GameTick::Command(deltatime, _) => GameTick::Tick(deltatime),
+        GameTick::Command(deltatime, _)
+        | GameTick::Repeat(deltatime, _, _)
+        | GameTick::Mouse(deltatime, _)
+        | GameTick::Key(deltatime, _) => GameTick::Tick(deltatime),
         tick => tick,
     }
 }
 
 /// Screen Manager general error
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ScreenManagerError {
     IoError(io::Error),
     CrosstermError(crossterm::ErrorKind),
-    InputManagerError(InputManagerError)
+    InputManagerError(InputManagerError),
+    SaveError(SaveError),
 }
 
 impl Error for ScreenManagerError {}
@@ -225,11 +432,12 @@ impl Error for ScreenManagerError {}
 impl fmt::Display for ScreenManagerError {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
-        write!(
-            f,
-            "Encountered error when using the Screen Manager: {:?}",
-            self
-        )
+        match self {
+            ScreenManagerError::IoError(error) => write!(f, "Could not draw to the terminal: {}", error),
+            ScreenManagerError::CrosstermError(error) => write!(f, "Could not set up the terminal: {}", error),
+            ScreenManagerError::InputManagerError(error) => write!(f, "Could not get input: {}", error),
+            ScreenManagerError::SaveError(error) => write!(f, "Could not save the game: {}", error),
+        }
     }
 
 }
@@ -258,11 +466,20 @@ impl From<InputManagerError> for ScreenManagerError {
 
 }
 
+impl From<SaveError> for ScreenManagerError {
+
+    fn from(error: SaveError) -> ScreenManagerError {
+        ScreenManagerError::SaveError(error)
+    }
+
+}
+
 #[cfg(test)]
 mod test {
     use ntest::timeout;
     use crate::utility::test_util::{crossterm_key, TestResult};
     use std::sync::mpsc;
+    use std::cell::Cell;
     use std::rc::Rc;
     use crate::game::Command;
     use std::collections::VecDeque;
@@ -270,15 +487,21 @@ mod test {
 
     static DUMMY_TICK: GameTick = GameTick::Tick(Duration::from_secs(1));
 
+    /// Size of the buffer headless screen managers draw to
+    const WIDTH: u16 = 40;
+    const HEIGHT: u16 = 20;
+
     /// Testing Screen that quits after a set amount of renders
     struct TestScreen {
         sx: mpsc::Sender<GameTick>,
         pub rx_rc: Rc<mpsc::Receiver<GameTick>>,
-        callbacks: VecDeque<BoxedCallback>,
+        callbacks: VecDeque<BoxedCallback<TestBackend>>,
         pub render_counter: i32,
+        /// How many times the screen has been saved
+        pub saves: Rc<Cell<u32>>,
     }
 
-    impl Screen for TestScreen {
+    impl Screen<TestBackend> for TestScreen {
 
         fn new() -> Self {
             let (sx, rx) = mpsc::channel();
@@ -287,32 +510,38 @@ mod test {
             TestScreen {
                 sx,
                 rx_rc,
-                callbacks: VecDeque::<BoxedCallback>::new(),
+                callbacks: VecDeque::<BoxedCallback<TestBackend>>::new(),
                 render_counter: 0,
+                saves: Rc::new(Cell::new(0)),
             }
         }
 
-        fn render(&mut self, _: &mut Frame, tick: GameTick) {
+        fn render(&mut self, _: &mut Frame<TestBackend>, tick: GameTick) {
             self.sx.send(tick).unwrap();
             self.render_counter += 1;
 
             // After 10 ticks, tell the screen manager to quit
             if self.render_counter > 10 {
-                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager| {
+                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<TestBackend>| {
                     s.should_quit = true;
                 }));
             }
         }
 
+        fn save(&self) -> std::result::Result<(), SaveError> {
+            self.saves.set(self.saves.get() + 1);
+            Ok(())
+        }
+
         fn tear_down(&mut self) {
             self.sx.send(DUMMY_TICK).unwrap();
         }
 
-        fn add_screen_manager_callback(&mut self, callback: BoxedCallback) {
+        fn add_screen_manager_callback(&mut self, callback: BoxedCallback<TestBackend>) {
             self.callbacks.push_front(callback)
         }
 
-        fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback> {
+        fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<TestBackend>> {
             self.callbacks.drain(0..).collect()
         }
 
@@ -322,11 +551,11 @@ mod test {
     struct TestPopup {
         sx: mpsc::Sender<GameTick>,
         pub rx_rc: Rc<mpsc::Receiver<GameTick>>,
-        callbacks: VecDeque<BoxedCallback>,
+        callbacks: VecDeque<BoxedCallback<TestBackend>>,
         pub render_counter: i32,
     }
 
-    impl Popup for TestPopup {
+    impl Popup<TestBackend> for TestPopup {
 
         fn new() -> Self {
             let (sx, rx) = mpsc::channel();
@@ -335,18 +564,18 @@ mod test {
             TestPopup {
                 sx,
                 rx_rc,
-                callbacks: VecDeque::<BoxedCallback>::new(),
+                callbacks: VecDeque::<BoxedCallback<TestBackend>>::new(),
                 render_counter: 0
             }
         }
 
-        fn render(&mut self, _: &mut Frame, tick: GameTick) {
+        fn render(&mut self, _: &mut Frame<TestBackend>, tick: GameTick) {
             self.sx.send(tick).unwrap();
             self.render_counter += 1;
 
             // After 10 ticks, tell screen manager to quit
             if self.render_counter > 10 {
-                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager| {
+                self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<TestBackend>| {
                     s.should_quit = true;
                 }));
             }
@@ -360,17 +589,18 @@ mod test {
             self.sx.send(DUMMY_TICK).unwrap();
         }
 
-        fn add_screen_manager_callback(&mut self, callback: BoxedCallback) {
+        fn add_screen_manager_callback(&mut self, callback: BoxedCallback<TestBackend>) {
             self.callbacks.push_front(callback)
         }
 
-        fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback> {
+        fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<TestBackend>> {
             self.callbacks.drain(0..).collect()
         }
 
     }
 
     /// Gets all ticks from a Receiver
+    #[allow(clippy::match_result_ok)]
     fn get_ticks_from_rx(rx: &mpsc::Receiver<GameTick>) -> Vec<GameTick> {
         let mut ticks = Vec::new();
 
@@ -398,9 +628,39 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_headless_draws_to_buffer() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(10, 4, vec!())?;
+        screen_manager.push_screen(TestScreen::new());
+
+        screen_manager.step(DUMMY_TICK)?;
+
+        assert_eq!(screen_manager.backend().buffer().area, tui::layout::Rect::new(0, 0, 10, 4));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signals_save_screens() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
+        let screen = TestScreen::new();
+        let saves = Rc::clone(&screen.saves);
+        screen_manager.push_screen(screen);
+
+        screen_manager.start_main_loop()?;
+        assert_eq!(saves.get(), 0);
+
+        screen_manager.should_quit = false;
+        screen_manager.interrupted.store(true, Ordering::Relaxed);
+        screen_manager.start_main_loop()?;
+        assert_eq!(saves.get(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_push_screen() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         let test_screen = TestScreen::new();
         screen_manager.push_screen(test_screen);
@@ -412,7 +672,7 @@ mod test {
 
     #[test]
     fn test_pop_screen() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         let test_screen_1 = TestScreen::new();
         let test_screen_2 = TestScreen::new();
@@ -436,7 +696,7 @@ mod test {
 
     #[test]
     fn test_push_popup() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         let test_popup = TestPopup::new();
         screen_manager.push_popup(test_popup);
@@ -448,7 +708,7 @@ mod test {
 
     #[test]
     fn test_pop_popup() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         let test_popup_1 = TestPopup::new();
         let test_popup_2 = TestPopup::new();
@@ -480,7 +740,7 @@ mod test {
         let test_popup_rx = test_popup.rx_rc.clone();
 
         {
-            let mut screen_manager = ScreenManager::new()?;
+            let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
             screen_manager.push_screen(test_screen);
             screen_manager.push_popup(test_popup);
@@ -496,7 +756,7 @@ mod test {
     /// Test commands handled correctly with screens
     #[test]
     fn test_render_screens() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         // create screens
         let screen_top = TestScreen::new();
@@ -528,7 +788,7 @@ mod test {
     /// Test commands handled correctly with screens and popups
     #[test]
     fn test_render_popup_and_screen() -> TestResult {
-        let mut screen_manager = ScreenManager::new()?;
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
 
         // create screens
         let screen_top = TestScreen::new();
@@ -573,7 +833,7 @@ mod test {
     #[test]
     #[timeout(2000)]
     fn test_main_loop_screen() {
-        let mut screen_manager = ScreenManager::debug_new(vec!(
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!(
             crossterm_key('k')
         )).unwrap();
 
@@ -595,11 +855,28 @@ mod test {
         assert_ne!(an_input, None);
     }
 
+    #[test]
+    fn test_input_context_of_top_view() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
+        assert_eq!(screen_manager.input_context(), InputContext::Game);
+
+        screen_manager.push_screen(TestScreen::new());
+        assert_eq!(screen_manager.input_context(), InputContext::Game);
+
+        screen_manager.push_popup(TestPopup::new());
+        assert_eq!(screen_manager.input_context(), InputContext::Menu);
+
+        screen_manager.pop_popup();
+        assert_eq!(screen_manager.input_context(), InputContext::Game);
+
+        Ok(())
+    }
+
     /// Test keyboard input handled correctly with just popups
     #[test]
     #[timeout(2000)]
     fn test_main_loop_popup() {
-        let mut screen_manager = ScreenManager::debug_new(vec!(
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!(
             crossterm_key('k')
         )).unwrap();
 
@@ -625,7 +902,7 @@ mod test {
     #[test]
     #[timeout(2000)]
     fn test_main_loop_screens_and_popups() {
-        let mut screen_manager = ScreenManager::debug_new(vec!(
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!(
             crossterm_key('k')
         )).unwrap();
 
@@ -654,4 +931,96 @@ mod test {
         assert_ne!(popup_input, None); // Popup should of received input
         assert_eq!(screen_input, None); // and not the screen
     }
+
+    #[test]
+    fn test_toggle_stats() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
+        let screen = TestScreen::new();
+        let screen_rx = screen.rx_rc.clone();
+        screen_manager.push_screen(screen);
+
+        let toggle = GameTick::Command(Duration::from_millis(16), Command::ToggleStats);
+        screen_manager.step(toggle)?;
+        assert!(screen_manager.is_showing_stats());
+
+        // The screen only gets the time that passed
+        assert_eq!(get_ticks_from_rx(&screen_rx), vec!(GameTick::Tick(Duration::from_millis(16))));
+
+        let title: String = (1..6)
+            .map(|x| screen_manager.backend().buffer().get(x, 0).symbol.clone())
+            .collect();
+        assert_eq!(title, "Stats");
+
+        screen_manager.step(toggle)?;
+        assert!(!screen_manager.is_showing_stats());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_without_drawing() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
+        let screen = TestScreen::new();
+        let screen_rx = screen.rx_rc.clone();
+        screen_manager.push_screen(screen);
+
+        let title = |screen_manager: &ScreenManager<TestBackend>| -> String {
+            (1..6).map(|x| screen_manager.backend().buffer().get(x, 0).symbol.clone()).collect()
+        };
+
+        // The tick is still handled, but the overlay isn't shown until the next frame is drawn
+        let toggle = GameTick::Command(Duration::from_millis(16), Command::ToggleStats);
+        screen_manager.step_without_drawing(toggle)?;
+        assert!(screen_manager.is_showing_stats());
+        assert_eq!(get_ticks_from_rx(&screen_rx), vec!(GameTick::Tick(Duration::from_millis(16))));
+        assert_eq!(title(&screen_manager), "     ");
+
+        screen_manager.step(DUMMY_TICK)?;
+        assert_eq!(title(&screen_manager), "Stats");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_due() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(WIDTH, HEIGHT, vec!())?;
+
+        screen_manager.frame_interval = Some(Duration::from_secs(60));
+        screen_manager.step(DUMMY_TICK)?;
+        assert!(!screen_manager.is_frame_due());
+
+        screen_manager.frame_interval = None;
+        assert!(screen_manager.is_frame_due());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_interval() {
+        assert_eq!(frame_interval(0), None);
+        assert_eq!(frame_interval(50), Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    #[timeout(2000)]
+    fn test_replay_source_gives_recorded_input() {
+        use crate::game::recording::{ReplaySource, ReplayTiming};
+
+        let recording = "# terminal-dungeon input recording\n0 key 0 down\n0 key 0 char 107\n";
+        let source = ReplaySource::from_reader(recording.as_bytes(), ReplayTiming::Fast, FakeSource::new(vec!())).unwrap();
+        let mut screen_manager = ScreenManager::init(TestBackend::new(WIDTH, HEIGHT), source).unwrap();
+        let screen = TestScreen::new();
+        let screen_rx = screen.rx_rc.clone();
+        screen_manager.push_screen(screen);
+
+        screen_manager.start_main_loop().unwrap();
+
+        // The recorded keys go through the keymap like typed ones
+        let commands: Vec<GameTick> = get_ticks_from_rx(&screen_rx).into_iter()
+            .filter(|tick| matches!(tick, GameTick::Command(..)))
+            .collect();
+        assert_eq!(commands.len(), 2);
+        assert!(tick_has_command(commands[0], Command::Down));
+        assert!(tick_has_command(commands[1], Command::Up));
+    }
 }
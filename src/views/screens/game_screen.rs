@@ -1,85 +1,771 @@
 use super::Frame;
-use crate::views::ScreenManager;
+use crate::views::{FrameStats, ScreenManager, TerminalBackend};
 use crate::utility::conversions;
+use tui::backend::Backend;
 use tui::layout::Rect;
 use tui::widgets::Block;
+use tui::widgets::Clear;
 use tui::widgets::Paragraph;
 use tui::widgets::Borders;
 
-use crate::game::Command;
+use crate::game::{Command, InputContext, MouseAction, MouseInput};
 use crate::game::GameTick;
+use crate::game::animation_gate::AnimationGate;
+use crate::game::save::{self, SaveError, SaveGame};
 use crate::views::Screen;
 use crate::views::screen_manager::BoxedCallback;
+use crate::views::popups::list_popup::ListPopup;
+use crate::views::popups::text_input_popup::TextInputPopup;
 
+use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::rc::Rc;
 
 use specs::Dispatcher;
-use specs::{DispatcherBuilder, World, WorldExt};
+use specs::{DispatcherBuilder, Entity, Join, World, WorldExt};
 
 use crate::entities::factory;
 use crate::systems::*;
 use crate::entities::component::*;
-use crate::world::map::{self, Map};
+use crate::world::map::{self, Map, Tile};
+use crate::world::path;
 use crate::utility::text_canvas::TextCanvas;
+use crate::utility::color_util::ColorDepth;
+use crate::utility::export;
+use crate::config;
+use crate::config::keymap::Keymap;
+use crate::config::theme::{ColorRole, Theme};
+
+extern crate nalgebra as na;
+use na::Vector2;
+
+/// Columns the popup for typing the player's name takes up, borders included
+const NAME_POPUP_WIDTH: u16 = 32;
+/// Widest the list of key bindings is drawn
+const HELP_POPUP_WIDTH: u16 = 40;
 
 /// Primary screen where game is played
 /// Handles the boiler palte for setting up a Specs World
-pub struct GameScreen<'a> {
+pub struct GameScreen<'a, B: Backend = TerminalBackend> {
     world: World,
     dispatcher: Dispatcher<'a, 'a>,
-    callbacks: VecDeque<BoxedCallback>,
+    callbacks: VecDeque<BoxedCallback<B>>,
+    /// Themes that can be switched between
+    themes: Vec<Theme>,
+    /// Index in `themes` of the theme in use
+    theme_index: usize,
+    /// Shown in the title of the main view, to tell the player what happened, until they do something else
+    message: Option<String>,
+    /// Holds back turns taken while moves are still animating
+    gate: AnimationGate,
+    /// Tile the mouse is pointing at, which the status line describes
+    hovered_tile: Option<(i32, i32)>,
+    /// What input is going to, such as a macro being recorded. Shown in the title alongside `message`
+    input_status: Option<String>,
+    /// Name typed into the naming popup, waiting to be given to the player
+    typed_name: Rc<RefCell<Option<String>>>,
 }
 
-impl Screen for GameScreen<'_> {
+impl<B: Backend + 'static> Screen<B> for GameScreen<'_, B> {
 
     fn new() -> Self {
         let (mut world, dispatcher) = init_game();
-        register_components(&mut world);
-        add_resources(&mut world);
-        make_player(&mut world);
 
         factory::make_dummy(&mut world);
+        factory::make_hostile_dummy(&mut world);
+
+        let themes = config::get().themes.clone();
+        let theme_index = config::get().starting_theme();
+        if let Some(theme) = themes.get(theme_index) {
+            world.insert(theme.clone());
+        }
 
         GameScreen {
             world,
             dispatcher,
             callbacks: VecDeque::new(),
+            themes,
+            theme_index,
+            message: None,
+            gate: AnimationGate::new(config::get().animation_mode),
+            hovered_tile: None,
+            input_status: None,
+            typed_name: Rc::new(RefCell::new(None)),
         }
     }
 
-    fn render(&mut self, mut frame: &mut Frame, tick: GameTick) {
+    fn render(&mut self, frame: &mut Frame<B>, tick: GameTick) {
+        let (tick, finish_animations) = self.gate.filter(tick, is_animating(&self.world));
+        if finish_animations {
+            finish_blocking_animations(&mut self.world);
+        }
+
+        if is_player_action(tick) {
+            self.message = None;
+        }
+
+        let tick = match tick {
+            GameTick::Mouse(deltatime, mouse) => {
+                self.handle_mouse(mouse, frame.size());
+                GameTick::Tick(deltatime)
+            },
+            tick => tick
+        };
+        let tick = self.travel_tick(tick);
+
         update_world_tick(&mut self.world, tick);
 
         if check_time_to_quit(tick) {
-            self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager| {
+            self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<B>| {
                 s.should_quit = true;
             }));
         }
 
+        if let GameTick::Command(_, Command::NextTheme) = tick {
+            self.next_theme();
+        }
+
+        if let GameTick::Command(_, Command::Name) = tick {
+            self.ask_for_name(frame.size());
+        }
+
+        if let GameTick::Command(_, Command::Help) = tick {
+            self.show_keys(frame.size());
+        }
+
+        if let GameTick::Command(_, Command::Target) = tick {
+            self.message = Some(target_next(&self.world));
+        }
+
+        if let GameTick::Command(_, Command::Fire) = tick {
+            if !is_target_in_view(&self.world) {
+                self.message = Some("Target something in view to fire at it".to_string());
+            }
+        }
+
+        let typed_name = self.typed_name.borrow_mut().take();
+        if let Some(name) = typed_name {
+            self.message = name_player(&self.world, name);
+        }
+
+        resize_canvases(&mut self.world, frame.size());
+        let before = (player_position(&self.world), visible_interruptions(&self.world));
         run_world(&mut self.world, &mut self.dispatcher);
-        draw_ui(&mut self.world, &mut frame);
+
+        if is_unexpected(&self.world, tick, before) {
+            self.add_screen_manager_callback(Box::new(|s: &mut ScreenManager<B>| {
+                s.stop_macro();
+            }));
+        }
+
+        if let GameTick::Command(_, Command::Screenshot) = tick {
+            self.message = Some(save_screenshot(&self.world));
+        }
+
+        let notices: Vec<&str> = self.input_status.iter().chain(&self.message).map(String::as_str).collect();
+        let notice = (!notices.is_empty()).then(|| notices.join(" - "));
+        let status = self.hovered_tile.map(|tile| describe_tile(&self.world, tile));
+        draw_ui(&mut self.world, frame, notice.as_deref(), status.as_deref());
+    }
+
+    fn is_busy(&self) -> bool {
+        is_animating(&self.world) || !self.world.read_storage::<Travel>().is_empty()
+    }
+
+    fn show_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    fn show_input_status(&mut self, status: Option<String>) {
+        self.input_status = status;
+    }
+
+    fn report_stats(&self, stats: &mut FrameStats) {
+        stats.system_times = self.world.read_resource::<SystemTimes>().all();
+        stats.entity_count = Some(self.world.entities().join().count());
+    }
+
+    fn save(&self) -> Result<(), SaveError> {
+        match save::save_path() {
+            Some(path) => self.to_save().save_to(&path),
+            None => Ok(())
+        }
     }
 
     fn tear_down(&mut self) {
     }
 
-    fn add_screen_manager_callback(&mut self, callback: BoxedCallback) {
+    fn add_screen_manager_callback(&mut self, callback: BoxedCallback<B>) {
         self.callbacks.push_front(callback)
     }
 
-    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback> {
+    fn get_screen_manager_callbacks(&mut self) -> VecDeque<BoxedCallback<B>> {
         self.callbacks.drain(0..).collect()
     }
 
 }
 
-fn check_time_to_quit(tick: GameTick) -> bool {
-    match tick {
-        GameTick::Command(_, Command::Quit) => true,
-        _ => false
+impl<B: Backend + 'static> GameScreen<'_, B> {
+
+    /// Carries on the game in `save`, putting the player back where they were with the tiles they'd seen
+    pub fn resume(&mut self, save: &SaveGame) {
+        let players = self.world.read_storage::<CommandResponse>();
+        let mut positions = self.world.write_storage::<Position>();
+        let mut names = self.world.write_storage::<Name>();
+
+        for (entity, _) in (&self.world.entities(), &players).join() {
+            let _ = positions.insert(entity, Position { vec2: Vector2::new(save.position.0, save.position.1) });
+            if let Some(name) = &save.name {
+                let _ = names.insert(entity, Name(name.clone()));
+            }
+        }
+
+        let mut map = self.world.write_resource::<Map>();
+        for (y, row) in save.revealed.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, &seen)| seen) {
+                map.reveal(conversions::as_i32(x), conversions::as_i32(y));
+            }
+        }
+        map.clear_visible();
+    }
+
+    /// Returns what's kept of the game when it's saved
+    fn to_save(&self) -> SaveGame {
+        let map = self.world.read_resource::<Map>();
+        let players = self.world.read_storage::<CommandResponse>();
+        let names = self.world.read_storage::<Name>();
+        let (width, height) = map.dimensions();
+
+        let name = (&players, &names).join().next().map(|(_, name)| name.0.clone());
+        let revealed = (0..conversions::as_i32(height))
+            .map(|y| (0..conversions::as_i32(width)).map(|x| map.is_revealed(x, y)).collect())
+            .collect();
+
+        SaveGame {
+            position: player_position(&self.world).map_or((0, 0), |position| (position[0], position[1])),
+            name,
+            revealed,
+        }
+    }
+
+    /// Switches to the next theme, going back to the first after the last
+    fn next_theme(&mut self) {
+        if self.themes.is_empty() {
+            return;
+        }
+
+        self.theme_index = (self.theme_index + 1) % self.themes.len();
+        self.world.insert(self.themes[self.theme_index].clone());
+    }
+
+    /// Opens a popup in the middle of `area` for the player to type their name into
+    fn ask_for_name(&mut self, area: Rect) {
+        let (width, height) = (NAME_POPUP_WIDTH.min(area.width), 3.min(area.height));
+        let popup_area = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+        let typed_name = Rc::clone(&self.typed_name);
+
+        self.add_screen_manager_callback(Box::new(move |s: &mut ScreenManager<B>| {
+            let typed_name = Rc::clone(&typed_name);
+            s.push_popup(TextInputPopup::with_prompt("What's your name?", popup_area, Box::new(move |_, name| {
+                *typed_name.borrow_mut() = Some(name);
+            })));
+        }));
+    }
+
+    /// Opens a list in the middle of `area` of the keys for each command, which scrolls when it doesn't fit
+    fn show_keys(&mut self, area: Rect) {
+        let lines = key_help(&config::get().keymap);
+        let rows = conversions::usize_to_u16(lines.len()).saturating_add(2);
+        let (width, height) = (HELP_POPUP_WIDTH.min(area.width), rows.min(area.height));
+        let popup_area = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+        let highlight = self.world.read_resource::<Theme>().colors(ColorRole::Highlight);
+
+        self.add_screen_manager_callback(Box::new(move |s: &mut ScreenManager<B>| {
+            s.push_popup(ListPopup::with_items("Keys", lines.clone(), popup_area).with_highlight(highlight));
+        }));
+    }
+
+    /// Returns the tick to run given the player's `tick`, taking the next step of any travel when the player isn't
+    /// doing anything else. Taking a turn by hand stops travelling, and a turn with a count walks that many steps
+    fn travel_tick(&mut self, tick: GameTick) -> GameTick {
+        match tick {
+            GameTick::Command(_, command) if command.is_turn() => {
+                self.world.write_storage::<Travel>().clear();
+                tick
+            },
+            GameTick::Command(deltatime, command) if command.run_direction().is_some() => {
+                run(&self.world, command);
+                self.travel_tick(GameTick::Tick(deltatime))
+            },
+            GameTick::Command(deltatime, Command::Explore) => {
+                self.message = explore(&self.world);
+                self.travel_tick(GameTick::Tick(deltatime))
+            },
+            GameTick::Command(deltatime, Command::TravelTo) => {
+                self.message = match self.hovered_tile {
+                    Some(tile) => travel_to(&self.world, tile),
+                    None => Some("Point at a tile with the mouse to travel there".to_string())
+                };
+                self.travel_tick(GameTick::Tick(deltatime))
+            },
+            GameTick::Repeat(deltatime, command, count) if command.is_turn() => {
+                walk(&self.world, command, count);
+                self.travel_tick(GameTick::Tick(deltatime))
+            },
+            GameTick::Repeat(deltatime, command, _) => self.travel_tick(GameTick::Command(deltatime, command)),
+            GameTick::Tick(deltatime) if !is_animating(&self.world) => {
+                if let Some(reason) = travel_interruption(&self.world) {
+                    self.world.write_storage::<Travel>().clear();
+                    self.message = Some(reason);
+                    return tick;
+                }
+
+                match next_travel_step(&self.world) {
+                    Ok(Some(command)) => GameTick::Command(deltatime, command),
+                    Ok(None) => tick,
+                    Err(reason) => {
+                        self.message = Some(reason);
+                        tick
+                    }
+                }
+            },
+            tick => tick
+        }
+    }
+
+    /// Clicking a tile walks the player there, and pointing at a tile describes it in the status line
+    fn handle_mouse(&mut self, mouse: MouseInput, area: Rect) {
+        let tile = screen_to_world(&self.world, mouse.column, mouse.row, area);
+
+        match mouse.action {
+            MouseAction::Click => {
+                self.hovered_tile = tile;
+                if let Some(tile) = tile {
+                    self.message = travel_to(&self.world, tile);
+                }
+            },
+            MouseAction::Hover => self.hovered_tile = tile,
+            MouseAction::ScrollUp | MouseAction::ScrollDown => ()
+        }
+    }
+
+}
+
+/// Returns the world tile under (`column`, `row`) of the terminal, going through the camera drawn on top there.
+/// Returns `None` if no camera's canvas is there, such as on a border
+fn screen_to_world(world: &World, column: u16, row: u16, area: Rect) -> Option<(i32, i32)> {
+    let map = world.read_resource::<Map>();
+    let cameras = world.read_storage::<Camera>();
+    let positions = world.read_storage::<Position>();
+    let canvases = world.read_storage::<TextCanvas>();
+
+    let mut views: Vec<(&Camera, &Position, &TextCanvas)> = (&cameras, &positions, &canvases).join().collect();
+    views.sort_by_key(|(camera, _, _)| camera.region);
+
+    // Views later in the list are drawn over earlier ones
+    views.into_iter().rev().find_map(|(camera, camera_position, canvas)| {
+        let rec = get_canvas_area(camera, area, map.dimensions());
+        let (left, top) = (rec.x + 1, rec.y + 1);
+        let (right, bottom) = (rec.right().saturating_sub(1), rec.bottom().saturating_sub(1));
+
+        if (left..right).contains(&column) && (top..bottom).contains(&row) {
+            let xy = (conversions::u16_to_usize(column - left), conversions::u16_to_usize(row - top));
+            Some(canvas_to_world(xy, camera, camera_position, canvas))
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns a line for each game command, naming it and the keys bound to it in `keymap`
+fn key_help(keymap: &Keymap) -> Vec<String> {
+    InputContext::Game.commands().iter()
+        .map(|&command| match keymap.keys(InputContext::Game, command).join(", ") {
+            keys if keys.is_empty() => format!("{:<13}unbound", command.name()),
+            keys => format!("{:<13}{}", command.name(), keys)
+        })
+        .collect()
+}
+
+/// Gives the player `name`, returning a message saying so. Names that are only spaces are ignored
+fn name_player(world: &World, name: String) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let players = world.read_storage::<CommandResponse>();
+    let mut names = world.write_storage::<Name>();
+
+    for (entity, _) in (&world.entities(), &players).join() {
+        let _ = names.insert(entity, Name(name.to_string()));
+    }
+
+    Some(format!("You are now called {}", name))
+}
+
+/// Things that stop the player travelling when they come into view, with what to tell the player
+const INTERRUPTIONS: [(ColorRole, &str); 2] = [
+    (ColorRole::Hostile, "You see something hostile"),
+    (ColorRole::Item, "You see an item"),
+];
+
+/// Starts the player walking to `tile`. Returns a message if there is no way there
+fn travel_to(world: &World, tile: (i32, i32)) -> Option<String> {
+    let map = world.read_resource::<Map>();
+
+    let found = start_travel(world, TravelMode::Path, |from| {
+        let steps = path::find_path(&map, (from[0], from[1]), tile)?;
+        Some(to_vectors(steps))
+    });
+
+    if found {
+        None
+    } else {
+        Some("Can't find a way there".to_string())
+    }
+}
+
+/// Starts the player exploring, walking to the closest place they haven't seen until there are none left. Returns a
+/// message if there is nowhere to explore
+fn explore(world: &World) -> Option<String> {
+    let map = world.read_resource::<Map>();
+
+    if start_travel(world, TravelMode::Explore, |from| explore_path(&map, from)) {
+        None
+    } else {
+        Some("Nothing left to explore".to_string())
+    }
+}
+
+/// Returns the path from `from` to the closest tile next to one that hasn't been seen
+fn explore_path(map: &Map, from: Vector2<i32>) -> Option<Vec<Vector2<i32>>> {
+    path::path_to_unexplored(map, (from[0], from[1])).map(to_vectors)
+}
+
+fn to_vectors(steps: Vec<(i32, i32)>) -> Vec<Vector2<i32>> {
+    steps.into_iter().map(|(x, y)| Vector2::new(x, y)).collect()
+}
+
+/// Starts the player walking `count` steps in the direction `command` moves, stopping early if something is in the way
+fn walk(world: &World, command: Command, count: u32) {
+    if let Some(offset) = direction_offset(command) {
+        start_travel(world, TravelMode::Path, |from| Some((1..=count).map(|i| from + offset * i as i32).collect()));
+    }
+}
+
+/// Starts the player running in the direction of the run command `command`
+fn run(world: &World, command: Command) {
+    if let Some(offset) = command.run_direction().and_then(direction_offset) {
+        start_travel(world, TravelMode::Run(offset), |from| Some(vec!(from + offset)));
+    }
+}
+
+/// Starts the player walking along the path `plan` gives from where they are, carrying on as `mode` says. Returns
+/// `false` if there's no path
+fn start_travel(world: &World, mode: TravelMode, plan: impl Fn(Vector2<i32>) -> Option<Vec<Vector2<i32>>>) -> bool {
+    let already_seen: Vec<Entity> = visible_interruptions(world).into_iter().map(|(entity, _)| entity).collect();
+    let players = world.read_storage::<CommandResponse>();
+    let positions = world.read_storage::<Position>();
+    let mut travels = world.write_storage::<Travel>();
+    let entities = world.entities();
+
+    let mut found = true;
+    for (entity, _, position) in (&entities, &players, &positions).join() {
+        match plan(position.vec2) {
+            Some(path) => {
+                let _ = travels.insert(entity, Travel::with_mode(mode, path, already_seen.clone()));
+            },
+            None => found = false
+        }
+    }
+
+    found
+}
+
+/// Returns why the player should stop travelling, if something has come into view since they set off
+fn travel_interruption(world: &World) -> Option<String> {
+    let spotted = visible_interruptions(world);
+    let travels = world.read_storage::<Travel>();
+
+    travels.join()
+        .find_map(|travel| spotted.iter().find(|(entity, _)| !travel.already_seen.contains(entity)))
+        .map(|(_, reason)| reason.to_string())
+}
+
+/// Returns the entities on tiles the player can see that stop them travelling, along with what to tell the player
+fn visible_interruptions(world: &World) -> Vec<(Entity, &'static str)> {
+    let map = world.read_resource::<Map>();
+    let positions = world.read_storage::<Position>();
+    let appearances = world.read_storage::<Appearance>();
+    let entities = world.entities();
+
+    (&entities, &positions, &appearances).join()
+        .filter(|(_, position, _)| map.is_visible(position.vec2[0], position.vec2[1]))
+        .filter_map(|(entity, _, appearance)| {
+            INTERRUPTIONS.iter()
+                .find(|(role, _)| appearance.role == Some(*role))
+                .map(|(_, reason)| (entity, *reason))
+        })
+        .collect()
+}
+
+/// Returns where the entities with `role` on tiles the player can see are
+fn visible_with_role(world: &World, role: ColorRole) -> Vec<Vector2<i32>> {
+    let map = world.read_resource::<Map>();
+    let positions = world.read_storage::<Position>();
+    let appearances = world.read_storage::<Appearance>();
+
+    (&positions, &appearances).join()
+        .filter(|(position, appearance)| {
+            appearance.role == Some(role) && map.is_visible(position.vec2[0], position.vec2[1])
+        })
+        .map(|(position, _)| position.vec2)
+        .collect()
+}
+
+/// Returns the command that moves the player along their travel path, removing the path once it is walked or blocked.
+/// Explorers plan their path again each step, and runners pick their next step once they've taken the last one,
+/// stopping beside items or while something hostile is in view. Returns an error saying why if exploring has nowhere
+/// left to go
+fn next_travel_step(world: &World) -> Result<Option<Command>, String> {
+    let items = visible_with_role(world, ColorRole::Item);
+    let hostile_in_view = !visible_with_role(world, ColorRole::Hostile).is_empty();
+    let map = world.read_resource::<Map>();
+    let players = world.read_storage::<CommandResponse>();
+    let positions = world.read_storage::<Position>();
+    let mut travels = world.write_storage::<Travel>();
+    let entities = world.entities();
+
+    let mut finished = vec!();
+    let mut step = Ok(None);
+
+    for (entity, _, position, travel) in (&entities, &players, &positions, &mut travels).join() {
+        match travel.mode {
+            TravelMode::Explore => match explore_path(&map, position.vec2) {
+                Some(path) => travel.path = path.into(),
+                None => {
+                    finished.push(entity);
+                    step = Err("Nothing left to explore".to_string());
+                    continue;
+                }
+            },
+            TravelMode::Run(heading) if travel.path.is_empty() => {
+                let (x, y) = (position.vec2[0], position.vec2[1]);
+                let beside_item = items.iter().any(|item| (item - position.vec2).abs().max() <= 1);
+
+                match path::run_direction(&map, (x, y), (heading[0], heading[1])).filter(|_| !beside_item && !hostile_in_view) {
+                    Some((dx, dy)) => {
+                        let heading = Vector2::new(dx, dy);
+                        travel.mode = TravelMode::Run(heading);
+                        travel.path.push_back(position.vec2 + heading);
+                    },
+                    None => {
+                        finished.push(entity);
+                        continue;
+                    }
+                }
+            },
+            _ => ()
+        }
+
+        match travel.next_step(position.vec2).and_then(|next| direction_to(position.vec2, next)) {
+            Some(command) => step = Ok(Some(command)),
+            None => finished.push(entity)
+        }
+    }
+
+    for entity in finished {
+        travels.remove(entity);
+    }
+
+    step
+}
+
+/// Returns how far `command` moves something, or `None` if it isn't a movement command
+fn direction_offset(command: Command) -> Option<Vector2<i32>> {
+    match command {
+        Command::Up => Some(Vector2::new(0, -1)),
+        Command::Down => Some(Vector2::new(0, 1)),
+        Command::Left => Some(Vector2::new(-1, 0)),
+        Command::Right => Some(Vector2::new(1, 0)),
+        _ => None
+    }
+}
+
+/// Returns the movement command that goes from `from` to the tile `to` next to it, or `None` if they aren't next
+/// to each other
+fn direction_to(from: Vector2<i32>, to: Vector2<i32>) -> Option<Command> {
+    [Command::Up, Command::Down, Command::Left, Command::Right].iter()
+        .copied()
+        .find(|&command| direction_offset(command) == Some(to - from))
+}
+
+/// Returns what the player knows is at `tile`, for the status line
+fn describe_tile(world: &World, (x, y): (i32, i32)) -> String {
+    let map = world.read_resource::<Map>();
+    let positions = world.read_storage::<Position>();
+    let appearances = world.read_storage::<Appearance>();
+    let movements = world.read_storage::<PositionAnimation>();
+    let progress = world.read_storage::<AnimationProgress>();
+    let names = world.read_storage::<Name>();
+
+    if !map.is_revealed(x, y) {
+        return format!("{}, {}: unexplored", x, y);
+    }
+
+    // Only what can be seen right now, where it's drawn, as things move around
+    let entity = (&positions, &appearances, movements.maybe(), progress.maybe(), names.maybe()).join()
+        .find(|(position, _, movement, progress, _)| {
+            map.is_visible(x, y) && position.drawn(*movement, *progress) == Vector2::new(x, y)
+        })
+        .map(|(_, appearance, _, _, name)| describe_entity(appearance, name));
+
+    let description = entity.unwrap_or_else(|| {
+        match map.get(x, y) {
+            Some(Tile::Wall) => "a wall",
+            Some(Tile::Blank) => "floor",
+            None => "nothing"
+        }.to_string()
+    });
+
+    format!("{}, {}: {}", x, y, description)
+}
+
+/// Moves the target on to the next closest thing the player can see, going back to the closest after the furthest.
+/// Returns a message saying what's targeted
+fn target_next(world: &World) -> String {
+    let player = player_position(world);
+    let map = world.read_resource::<Map>();
+    let players = world.read_storage::<CommandResponse>();
+    let positions = world.read_storage::<Position>();
+    let appearances = world.read_storage::<Appearance>();
+    let names = world.read_storage::<Name>();
+    let mut targeted = world.write_storage::<Targeted>();
+    let entities = world.entities();
+
+    let candidates = (&entities, &positions, &appearances, names.maybe(), !&players);
+    let mut in_view: Vec<(Entity, Vector2<i32>, String)> = candidates.join()
+        .filter(|(_, position, _, _, _)| map.is_visible(position.vec2[0], position.vec2[1]))
+        .map(|(entity, position, appearance, name, _)| (entity, position.vec2, describe_entity(appearance, name)))
+        .collect();
+    in_view.sort_by_key(|(_, position, _)| {
+        let offset = player.map_or(*position, |player| position - player);
+        (offset.dot(&offset), position[1], position[0])
+    });
+
+    let current = in_view.iter().position(|(entity, _, _)| targeted.contains(*entity));
+    targeted.clear();
+
+    let next = match current {
+        Some(i) => in_view.get(i + 1).or_else(|| in_view.first()),
+        None => in_view.first()
+    };
+
+    match next {
+        Some((entity, _, description)) => {
+            let _ = targeted.insert(*entity, Targeted);
+            format!("You target {}", description)
+        },
+        None => "There's nothing in view to target".to_string()
+    }
+}
+
+/// Returns `true` if the player can see what they've targeted
+fn is_target_in_view(world: &World) -> bool {
+    let map = world.read_resource::<Map>();
+    let positions = world.read_storage::<Position>();
+    let targeted = world.read_storage::<Targeted>();
+
+    (&positions, &targeted).join().any(|(position, _)| map.is_visible(position.vec2[0], position.vec2[1]))
+}
+
+/// Returns what to call an entity that looks like `appearance`, such as `"something hostile (g)"`
+fn describe_entity(appearance: &Appearance, name: Option<&Name>) -> String {
+    let description = name.map_or_else(|| describe_role(appearance.role), |name| name.0.as_str());
+    format!("{} ({})", description, appearance.icon)
+}
+
+fn describe_role(role: Option<ColorRole>) -> &'static str {
+    match role {
+        Some(ColorRole::Player) => "you",
+        Some(ColorRole::Hostile) => "something hostile",
+        Some(ColorRole::Friendly) => "someone friendly",
+        Some(ColorRole::Item) => "an item",
+        _ => "something"
+    }
+}
+
+/// Saves what the main camera shows to the screenshot folder. Returns a message saying where it was saved, or why it
+/// couldn't be
+fn save_screenshot(world: &World) -> String {
+    let color_depth = *world.read_resource::<ColorDepth>();
+    let cameras = world.read_storage::<Camera>();
+    let canvases = world.read_storage::<TextCanvas>();
+
+    let canvas = (&cameras, &canvases).join()
+        .min_by_key(|(camera, _)| camera.region)
+        .map(|(_, canvas)| canvas);
+
+    let canvas = match canvas {
+        Some(canvas) => canvas,
+        None => return "Nothing to take a screenshot of".to_string()
+    };
+
+    let directory = &config::get().screenshot_dir;
+    match export::save_screenshot(canvas, color_depth, config::get().wide_glyphs, directory) {
+        Ok(_) => format!("Saved screenshot to {}", directory.display()),
+        Err(error) => format!("Couldn't save screenshot: {}", error)
+    }
+}
+
+/// Returns where the player is, or `None` if there is no player
+fn player_position(world: &World) -> Option<Vector2<i32>> {
+    let players = world.read_storage::<CommandResponse>();
+    let positions = world.read_storage::<Position>();
+
+    (&players, &positions).join().next().map(|(_, position)| position.vec2)
+}
+
+/// Returns `true` if running the world with `tick` went differently than the player would expect, given where the
+/// player was and what could be seen `before`: a move was blocked, or something hostile or an item came into view
+fn is_unexpected(world: &World, tick: GameTick, before: (Option<Vector2<i32>>, Vec<(Entity, &str)>)) -> bool {
+    let (position, seen) = before;
+
+    let blocked = matches!(tick, GameTick::Command(_, command) if command.is_turn()) && player_position(world) == position;
+    let spotted = visible_interruptions(world).iter()
+        .any(|(entity, _)| !seen.iter().any(|(seen_entity, _)| seen_entity == entity));
+
+    blocked || spotted
+}
+
+/// Returns `true` if any animations that block input are playing
+fn is_animating(world: &World) -> bool {
+    world.read_storage::<BlocksInput>().join().next().is_some()
+}
+
+/// Skips to the end of every animation that blocks input. They are removed the next time the world runs
+fn finish_blocking_animations(world: &mut World) {
+    let blocks_input = world.read_storage::<BlocksInput>();
+    let mut progress = world.write_storage::<AnimationProgress>();
+
+    for (_, progress) in (&blocks_input, &mut progress).join() {
+        progress.current = progress.total;
     }
 }
 
+/// Returns `true` if the player did something with `tick`, rather than time just passing or the mouse pointing
+fn is_player_action(tick: GameTick) -> bool {
+    matches!(
+        tick,
+        GameTick::Command(..) | GameTick::Repeat(..) | GameTick::Mouse(_, MouseInput { action: MouseAction::Click, .. })
+    )
+}
+
+fn check_time_to_quit(tick: GameTick) -> bool {
+    matches!(tick, GameTick::Command(_, Command::Quit))
+}
+
 /// Handling specs ECS
 fn init_game<'a>() -> (World, Dispatcher<'a, 'a>) {
     let mut world = World::new();
@@ -100,30 +786,28 @@ fn register_components(world: &mut World) {
     world.register::<Follow>();
     world.register::<Position>();
     world.register::<Collision>();
+    world.register::<TextCanvas>();
+    world.register::<Targeted>();
+    world.register::<Name>();
+    world.register::<Viewshed>();
+    world.register::<ModifierAnimation>();
+    world.register::<Particle>();
+    world.register::<PositionAnimation>();
+    world.register::<AnimationProgress>();
+    world.register::<BlocksInput>();
+    world.register::<Travel>();
 }
 
 fn add_resources(world: &mut World) {
     let map = initialize_map();
-
-    let canvas = create_canvas(&map);
-    world.insert(canvas);
-
     world.insert(map);
 
-    world.insert(GameTick::default());
-}
-
-fn create_canvas(map: &Map) -> TextCanvas {
-    let (terminal_width, terminal_height) = match crossterm::terminal::size() {
-        Ok((width, height)) => (conversions::u16_to_usize(width), conversions::u16_to_usize(height)),
-        _ => (0, 0)
-    };
-    let (map_width, map_height) = map.dimensions();
+    world.insert(config::get().color_depth());
+    world.insert(Theme::default());
+    world.insert(config::get().tile_font);
 
-    let canvas_width = std::cmp::min(terminal_width, map_width);
-    let canvas_height = std::cmp::min(terminal_height, map_height);
-
-    TextCanvas::with_size(canvas_width, canvas_height)
+    world.insert(GameTick::default());
+    world.insert(SystemTimes::default());
 }
 
 fn initialize_map() -> Map {
@@ -133,13 +817,21 @@ fn initialize_map() -> Map {
 fn make_player(world: &mut World) {
     let player = factory::make_player(world);
     let _ = factory::make_camera(player, world);
+    let _ = factory::make_minimap_camera(player, world);
 }
 
+/// Each system is `Timed` under its name, for the stats overlay
 fn setup_dispatch<'a>() -> Dispatcher<'a, 'a> {
+    let command_system = CommandSystem { move_animation: config::get().move_animation };
+
     DispatcherBuilder::new()
-        .with(CommandSystem, "Command", &[])
-        .with(FollowSystem, "Follow", &["Command"])
-        .with_thread_local(TextRenderSystem)
+        .with(Timed::new("Command", command_system), "Command", &[])
+        .with(Timed::new("PositionAnimation", PositionAnimationSystem), "PositionAnimation", &["Command"])
+        .with(Timed::new("Follow", FollowSystem), "Follow", &["PositionAnimation"])
+        .with(Timed::new("Visibility", VisibilitySystem), "Visibility", &["PositionAnimation"])
+        .with(Timed::new("ModifierAnimation", ModifierAnimationSystem), "ModifierAnimation", &[])
+        .with(Timed::new("Particle", ParticleSystem), "Particle", &["Command"])
+        .with_thread_local(Timed::new("TextRender", TextRenderSystem))
         .build()
 }
 
@@ -149,23 +841,556 @@ fn update_world_tick(world: &mut World, tick: GameTick) {
 }
 
 fn run_world(world: &mut World, dispatcher: &mut Dispatcher) {
-    dispatcher.dispatch(&world);
+    dispatcher.dispatch(world);
     world.maintain();
 }
 
-fn draw_ui(world: &mut World, frame: &mut Frame) {
-    let canvas = world.read_resource::<TextCanvas>();
+/// Resizes each camera's canvas to fit the screen region it draws into
+fn resize_canvases(world: &mut World, area: Rect) {
+    let map = world.read_resource::<Map>();
+    let cameras = world.read_storage::<Camera>();
+    let mut canvases = world.write_storage::<TextCanvas>();
+
+    for (camera, canvas) in (&cameras, &mut canvases).join() {
+        let canvas_area = get_canvas_area(camera, area, map.dimensions());
+
+        // Leave room for the border
+        let width = canvas_area.width.saturating_sub(2);
+        let height = canvas_area.height.saturating_sub(2);
+
+        canvas.resize(conversions::u16_to_usize(width), conversions::u16_to_usize(height));
+    }
+}
+
+/// Draws each camera's canvas in its region. `message` goes in the main view's title, and `status` along the bottom
+/// of the main view
+fn draw_ui<B: Backend>(world: &mut World, frame: &mut Frame<B>, message: Option<&str>, status: Option<&str>) {
+    let map = world.read_resource::<Map>();
+    let color_depth = *world.read_resource::<ColorDepth>();
+    let cameras = world.read_storage::<Camera>();
+    let canvases = world.read_storage::<TextCanvas>();
+
+    let mut views: Vec<(&Camera, &TextCanvas)> = (&cameras, &canvases).join().collect();
+    views.sort_by_key(|(camera, _)| camera.region);
+
+    let mut main_area = None;
+
+    for (camera, canvas) in views {
+        let map_text = canvas.as_styled_text(color_depth, config::get().wide_glyphs);
+        let title = match message {
+            Some(message) if camera.region == ScreenRegion::Main => {
+                format!("{} - {}", get_region_title(camera.region), message)
+            },
+            _ => get_region_title(camera.region).to_string()
+        };
+
+        let map_display = Paragraph::new(map_text)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+            );
+
+        let rec = get_canvas_area(camera, frame.size(), map.dimensions());
+
+        // Views drawn on top of others shouldn't let what's underneath show through
+        if camera.region != ScreenRegion::Main {
+            frame.render_widget(Clear, rec);
+        } else {
+            main_area = Some(rec);
+        }
+        frame.render_widget(map_display, rec);
+    }
+
+    if let (Some(status), Some(rec)) = (status, main_area) {
+        let status_line = Rect::new(rec.x + 1, rec.bottom().saturating_sub(1), rec.width.saturating_sub(2), 1);
+        frame.render_widget(Paragraph::new(status), status_line);
+    }
+}
+
+/// Returns the part of `area` that `region` draws into
+fn get_region_area(region: ScreenRegion, area: Rect) -> Rect {
+    match region {
+        ScreenRegion::Main => area,
+        ScreenRegion::Minimap => {
+            let (width, height) = (area.width / 4, area.height / 3);
+            Rect::new(area.right() - width, area.y, width, height)
+        }
+    }
+}
+
+/// Returns the area, including the border, that `camera`'s canvas is drawn in.
+/// Shrinks the region to not be larger than the map, keeping it in the region's corner of the screen
+fn get_canvas_area(camera: &Camera, area: Rect, map_dimensions: (usize, usize)) -> Rect {
+    let region_area = get_region_area(camera.region, area);
+
+    let scale = camera.scale.max(1);
+    let (map_width, map_height) = map_dimensions;
+    let map_width = map_width.div_ceil(scale) + 2;
+    let map_height = map_height.div_ceil(scale) + 2;
+
+    let width = conversions::u16_to_usize(region_area.width).min(map_width) as u16;
+    let height = conversions::u16_to_usize(region_area.height).min(map_height) as u16;
+
+    let x = match camera.region {
+        ScreenRegion::Minimap => region_area.right() - width,
+        ScreenRegion::Main => region_area.x
+    };
+
+    Rect::new(x, region_area.y, width, height)
+}
+
+fn get_region_title(region: ScreenRegion) -> &'static str {
+    match region {
+        ScreenRegion::Main => "map!",
+        ScreenRegion::Minimap => "minimap",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::InputContext;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use specs::Builder;
+    use crate::utility::test_util::{assert_snapshot, buffer_to_string, game_screen_test, TestResult};
+    use std::time::Duration;
+
+    /// Returns where the player is
+    fn player_position(world: &World) -> (i32, i32) {
+        let position = super::player_position(world).expect("There is a player");
+        (position[0], position[1])
+    }
+
+    #[test]
+    fn test_first_frame_snapshot() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(60, 24, vec!())?;
+        screen_manager.push_screen(GameScreen::new());
+
+        screen_manager.step(GameTick::Tick(Duration::from_millis(16)))?;
+        let frame = buffer_to_string(screen_manager.backend().buffer());
+
+        // The camera starts on the player and inside the map, so nothing off the map is drawn
+        assert!(!frame.contains('?'));
+        assert!(frame.lines().nth(5).is_some_and(|row| row.starts_with("│    @")));
+        assert_snapshot("game_screen_first_frame", &frame);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_naming_the_player() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(60, 24, vec!())?;
+        screen_manager.push_screen(GameScreen::new());
+        let deltatime = Duration::from_millis(16);
+
+        screen_manager.step(GameTick::Command(deltatime, Command::Name))?;
+        assert_eq!(screen_manager.input_context(), InputContext::Text);
+
+        for character in "Al".chars() {
+            let key = KeyEvent::new(KeyCode::Char(character), KeyModifiers::NONE);
+            screen_manager.step(GameTick::Key(deltatime, key))?;
+        }
+        screen_manager.step(GameTick::Command(deltatime, Command::Confirm))?;
+        screen_manager.step(GameTick::Tick(deltatime))?;
+
+        assert_eq!(screen_manager.input_context(), InputContext::Game);
+        assert!(buffer_to_string(screen_manager.backend().buffer()).contains("You are now called Al"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_help_lists_keys() -> TestResult {
+        let mut screen_manager = ScreenManager::headless(60, 10, vec!())?;
+        screen_manager.push_screen(GameScreen::new());
+        let deltatime = Duration::from_millis(16);
+
+        screen_manager.step(GameTick::Command(deltatime, Command::Help))?;
+        screen_manager.step(GameTick::Tick(deltatime))?;
+        assert_eq!(screen_manager.input_context(), InputContext::Menu);
+        let shown = buffer_to_string(screen_manager.backend().buffer());
+        assert!(shown.contains("up           k, up"));
+        assert!(!shown.contains("quit"));
+
+        // The first key is highlighted with the theme's colors
+        let highlight = Theme::default().colors(ColorRole::Highlight);
+        let highlighted: String = screen_manager.backend().buffer().content.iter()
+            .filter(|cell| cell.bg == highlight.background)
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert!(highlighted.starts_with("up"));
+
+        let scroll = MouseInput { action: MouseAction::ScrollDown, column: 0, row: 0 };
+        for _ in 0..10 {
+            screen_manager.step(GameTick::Mouse(deltatime, scroll))?;
+        }
+        assert!(buffer_to_string(screen_manager.backend().buffer()).contains("quit         ctrl+c, q"));
+
+        screen_manager.step(GameTick::Command(deltatime, Command::Cancel))?;
+        screen_manager.step(GameTick::Tick(deltatime))?;
+        assert_eq!(screen_manager.input_context(), InputContext::Game);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_named_player_is_described_by_name() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        assert_eq!(name_player(&screen.world, "  ".to_string()), None);
+        assert_eq!(name_player(&screen.world, " Al ".to_string()).as_deref(), Some("You are now called Al"));
+
+        let (x, y) = player_position(&screen.world);
+        assert_eq!(describe_tile(&screen.world, (x, y)), format!("{}, {}: Al (@)", x, y));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_click_walks_player_there() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        let start = player_position(&screen.world);
+        let goal = (start.0 + 2, start.1);
+
+        // Find where the goal is drawn
+        let area = terminal.size()?;
+        let (column, row) = (0..area.width)
+            .flat_map(|column| (0..area.height).map(move |row| (column, row)))
+            .find(|&(column, row)| screen_to_world(&screen.world, column, row, area) == Some(goal))
+            .expect("Goal is on screen");
+
+        let click = MouseInput { action: MouseAction::Click, column, row };
+        terminal.draw(|f| screen.render(f, GameTick::Mouse(tick.deltatime(), click)))?;
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(player_position(&screen.world), goal);
+        assert_eq!(screen.hovered_tile, Some(goal));
+        assert_eq!(describe_tile(&screen.world, goal), format!("{}, {}: you (@)", goal.0, goal.1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_moving_stops_travel() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        let start = player_position(&screen.world);
+        assert_eq!(travel_to(&screen.world, (start.0 + 3, start.1)), None);
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::Up)))?;
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(player_position(&screen.world), (start.0, start.1 - 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_walks_until_stopped() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        let start = player_position(&screen.world);
+        terminal.draw(|f| screen.render(f, GameTick::Repeat(tick.deltatime(), Command::Down, 3)))?;
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+        assert_eq!(player_position(&screen.world), (start.0, start.1 + 3));
+
+        // Walking into a wall stops the walk
+        terminal.draw(|f| screen.render(f, GameTick::Repeat(tick.deltatime(), Command::Right, 20)))?;
+        for _ in 0..30 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+        assert!(player_position(&screen.world).0 < start.0 + 20);
+        assert!(screen.world.read_storage::<Travel>().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hostile_in_view_stops_travel() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        // Put a hostile right below the player
+        let start = player_position(&screen.world);
+        {
+            let appearances = screen.world.read_storage::<Appearance>();
+            let mut positions = screen.world.write_storage::<Position>();
+            for (appearance, position) in (&appearances, &mut positions).join() {
+                if appearance.role == Some(ColorRole::Hostile) {
+                    position.vec2 = Vector2::new(start.0, start.1 + 2);
+                }
+            }
+        }
+        assert!(!visible_interruptions(&screen.world).is_empty());
+
+        // Walking while a hostile is already in view is fine
+        walk(&screen.world, Command::Left, 1);
+        terminal.draw(|f| screen.render(f, tick))?;
+        assert_eq!(player_position(&screen.world), (start.0 - 1, start.1));
+        assert_eq!(screen.message, None);
+
+        // A walk that started before the hostile was seen stops
+        {
+            let players = screen.world.read_storage::<CommandResponse>();
+            let mut travels = screen.world.write_storage::<Travel>();
+            for (entity, _) in (&screen.world.entities(), &players).join() {
+                travels.insert(entity, Travel::new(vec!(Vector2::new(start.0 - 2, start.1)), vec!()))?;
+            }
+        }
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        assert_eq!(player_position(&screen.world), (start.0 - 1, start.1));
+        assert_eq!(screen.message.as_deref(), Some("You see something hostile"));
+        assert!(screen.world.read_storage::<Travel>().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explore_until_nothing_left() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+
+        // Nothing to interrupt exploring
+        let others: Vec<Entity> = {
+            let appearances = screen.world.read_storage::<Appearance>();
+            (&screen.world.entities(), &appearances).join()
+                .filter(|(_, appearance)| appearance.role == Some(ColorRole::Hostile))
+                .map(|(entity, _)| entity)
+                .collect()
+        };
+        screen.world.delete_entities(&others)?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::Explore)))?;
+        for _ in 0..2000 {
+            if screen.message.is_some() {
+                break;
+            }
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(screen.message.as_deref(), Some("Nothing left to explore"));
+        let position = player_position(&screen.world);
+        assert_eq!(path::path_to_unexplored(&screen.world.read_resource::<Map>(), position), None);
+        assert!(screen.world.read_storage::<Travel>().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sight_follows_drawn_position() -> TestResult {
+        let (mut terminal, mut screen, _) = game_screen_test()?;
+        let deltatime = Duration::from_millis(10);
+
+        terminal.draw(|f| screen.render(f, GameTick::Tick(deltatime)))?;
+        let start = player_position(&screen.world);
+
+        // Part way through the move, the player is on the next tile but still drawn on the first
+        terminal.draw(|f| screen.render(f, GameTick::Command(deltatime, Command::Right)))?;
+        assert_eq!(player_position(&screen.world), (start.0 + 1, start.1));
+        assert_eq!(describe_tile(&screen.world, start), format!("{}, {}: you (@)", start.0, start.1));
+
+        // Sight reaches 10 tiles, so this tile is only in view from the next tile
+        let edge = (start.0 + 11, start.1);
+        assert!(!screen.world.read_resource::<Map>().is_visible(edge.0, edge.1));
+
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, GameTick::Tick(deltatime)))?;
+        }
+        assert!(screen.world.read_resource::<Map>().is_visible(edge.0, edge.1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_before_explore() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+
+        terminal.draw(|f| screen.render(f, tick))?;
+        terminal.draw(|f| screen.render(f, GameTick::Repeat(tick.deltatime(), Command::Explore, 3)))?;
+
+        assert_eq!(screen.message, None);
+        let travels = screen.world.read_storage::<Travel>();
+        assert!(travels.join().any(|travel| travel.mode == TravelMode::Explore));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_travel_to_needs_a_tile() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::TravelTo)))?;
+        assert_eq!(screen.message.as_deref(), Some("Point at a tile with the mouse to travel there"));
+
+        let start = player_position(&screen.world);
+        screen.hovered_tile = Some((start.0, start.1 + 2));
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::TravelTo)))?;
+        for _ in 0..5 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(screen.message, None);
+        assert_eq!(player_position(&screen.world), (start.0, start.1 + 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_stops_beside_wall() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        // The room has a wall every 5 tiles, so running stops beside the next one
+        let start = player_position(&screen.world);
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::RunRight)))?;
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(player_position(&screen.world), (5, start.1));
+        assert!(screen.world.read_storage::<Travel>().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_stops_with_hostile_in_view() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        // Put a hostile right below the player, so it has been seen before setting off
+        let start = (2, 7);
+        {
+            let appearances = screen.world.read_storage::<Appearance>();
+            let mut positions = screen.world.write_storage::<Position>();
+            for (appearance, position) in (&appearances, &mut positions).join() {
+                match appearance.role {
+                    Some(ColorRole::Player) => position.vec2 = Vector2::new(start.0, start.1),
+                    Some(ColorRole::Hostile) => position.vec2 = Vector2::new(start.0, start.1 + 1),
+                    _ => ()
+                }
+            }
+        }
+        terminal.draw(|f| screen.render(f, tick))?;
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::RunRight)))?;
+        for _ in 0..10 {
+            terminal.draw(|f| screen.render(f, tick))?;
+        }
+
+        assert_eq!(player_position(&screen.world), (start.0 + 1, start.1));
+        assert!(screen.world.read_storage::<Travel>().is_empty());
+
+        Ok(())
+    }
 
-    let map_text = (*canvas).as_styled_text();
-    let map_display = Paragraph::new(map_text)
-        .block(
-            Block::default()
-                .title("map!")
-                .borders(Borders::ALL)
-        );
+    #[test]
+    fn test_message_lasts_until_next_action() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
 
-    let (width, height) = (*canvas).dimensions();
-    let rec = Rect::new(0, 0, width as u16, height as u16);
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::TravelTo)))?;
+        assert!(screen.message.is_some());
 
-    frame.render_widget(map_display, rec);
+        // Time passing and pointing at tiles leaves it
+        let hover = MouseInput { action: MouseAction::Hover, column: 0, row: 0 };
+        terminal.draw(|f| screen.render(f, tick))?;
+        terminal.draw(|f| screen.render(f, GameTick::Mouse(tick.deltatime(), hover)))?;
+        assert!(screen.message.is_some());
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::Up)))?;
+        assert_eq!(screen.message, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocked_move_stops_macro() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+        screen.get_screen_manager_callbacks();
+
+        // Moving freely goes as expected, and the screen is busy while the move animates
+        terminal.draw(|f| screen.render(f, GameTick::Command(Duration::from_millis(16), Command::Right)))?;
+        assert!(screen.get_screen_manager_callbacks().is_empty());
+        assert!(screen.is_busy());
+
+        // There's a wall below
+        terminal.draw(|f| screen.render(f, tick))?;
+        terminal.draw(|f| screen.render(f, GameTick::Command(tick.deltatime(), Command::Down)))?;
+        assert_eq!(screen.get_screen_manager_callbacks().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_cycles_closest_first() -> TestResult {
+        let (mut terminal, mut screen, _) = game_screen_test()?;
+        let deltatime = Duration::from_millis(16);
+        terminal.draw(|f| screen.render(f, GameTick::Tick(deltatime)))?;
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(deltatime, Command::Target)))?;
+        assert_eq!(screen.message.as_deref(), Some("You target something hostile (g)"));
+
+        // The target is drawn reversed on the map and the minimap, and nothing else is
+        let reversed: Vec<&str> = terminal.backend().buffer().content.iter()
+            .filter(|cell| cell.modifier.contains(tui::style::Modifier::REVERSED))
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert_eq!(reversed, vec!("g", "g"));
+
+        // Something closer comes into view, which is targeted after the furthest
+        let item = Appearance {
+            icon: '!',
+            foreground: tui::style::Color::Yellow,
+            background: tui::style::Color::Reset,
+            modifiers: vec!(),
+            role: Some(ColorRole::Item)
+        };
+        screen.world.create_entity().with(Position { vec2: Vector2::new(6, 4) }).with(item).build();
+
+        terminal.draw(|f| screen.render(f, GameTick::Command(deltatime, Command::Target)))?;
+        assert_eq!(screen.message.as_deref(), Some("You target an item (!)"));
+        terminal.draw(|f| screen.render(f, GameTick::Command(deltatime, Command::Target)))?;
+        assert_eq!(screen.message.as_deref(), Some("You target something hostile (g)"));
+        assert_eq!(screen.world.read_storage::<Targeted>().count(), 1);
+
+        // Firing at the target draws the shot's trail
+        terminal.draw(|f| screen.render(f, GameTick::Command(deltatime, Command::Fire)))?;
+        terminal.draw(|f| screen.render(f, GameTick::Tick(deltatime)))?;
+        assert!(buffer_to_string(terminal.backend().buffer()).contains('='));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_saved_game() -> TestResult {
+        let (mut terminal, mut screen, tick) = game_screen_test()?;
+        terminal.draw(|f| screen.render(f, tick))?;
+        name_player(&screen.world, "Al".to_string());
+
+        let mut saved = screen.to_save();
+        saved.position = (2, 2);
+        assert_eq!(saved.name.as_deref(), Some("Al"));
+        assert!(saved.revealed.iter().flatten().any(|&seen| seen));
+
+        let (_, mut resumed, _) = game_screen_test()?;
+        resumed.resume(&saved);
+        assert_eq!(player_position(&resumed.world), (2, 2));
+        assert_eq!(resumed.to_save(), saved);
+
+        Ok(())
+    }
 }
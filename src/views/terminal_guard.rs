@@ -0,0 +1,52 @@
+use crossterm::cursor::Show;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use std::io::{self, Write};
+use std::panic;
+
+/// Takes over the terminal for drawing: raw mode, the alternate screen and mouse capture. The terminal is put back how
+/// it was when the guard is dropped, including when the game returns an error or panics
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+
+    /// Sets up the terminal for drawing, and makes panics put it back before printing
+    pub fn new() -> crossterm::Result<Self> {
+        install_panic_hook();
+
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard { _private: () };
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Clear(ClearType::All))?;
+
+        Ok(guard)
+    }
+
+}
+
+impl Drop for TerminalGuard {
+
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+
+}
+
+/// Puts the terminal back how it was before `TerminalGuard` took it over. Doing it more than once is harmless
+pub fn restore() -> crossterm::Result<()> {
+    execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen, Show)?;
+    terminal::disable_raw_mode()
+}
+
+/// Restores the terminal before the panic is printed. Otherwise the message goes to the alternate screen and is lost
+/// when the guard leaves it
+fn install_panic_hook() {
+    let print_panic = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        print_panic(info);
+    }));
+}
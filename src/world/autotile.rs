@@ -0,0 +1,111 @@
+use crate::utility::conversions;
+use crate::utility::grid::Grid;
+use super::map::Tile;
+
+/// Bit set in a wall's connections if the tile above it is a wall
+const UP: u8 = 1;
+/// Bit set in a wall's connections if the tile below it is a wall
+const DOWN: u8 = 2;
+/// Bit set in a wall's connections if the tile left of it is a wall
+const LEFT: u8 = 4;
+/// Bit set in a wall's connections if the tile right of it is a wall
+const RIGHT: u8 = 8;
+
+/// Returns which of the orthogonal neighbours of (x, y) are walls, as a combination of `UP`, `DOWN`, `LEFT` and
+/// `RIGHT`. Out of bounds tiles are not walls
+pub fn wall_connections(tiles: &Grid<Tile>, x: i32, y: i32) -> u8 {
+    [(0, -1, UP), (0, 1, DOWN), (-1, 0, LEFT), (1, 0, RIGHT)].iter()
+        .filter(|&&(dx, dy, _)| tiles.get(x + dx, y + dy) == Some(&Tile::Wall))
+        .fold(0, |connections, &(_, _, bit)| connections | bit)
+}
+
+/// Returns the box drawing glyph that joins up with the walls in `connections`
+pub fn box_glyph(connections: u8) -> char {
+    match connections {
+        c if c == UP | DOWN | LEFT | RIGHT => '╬',
+        c if c == UP | DOWN | RIGHT => '╠',
+        c if c == UP | DOWN | LEFT => '╣',
+        c if c == DOWN | LEFT | RIGHT => '╦',
+        c if c == UP | LEFT | RIGHT => '╩',
+        c if c == DOWN | RIGHT => '╔',
+        c if c == DOWN | LEFT => '╗',
+        c if c == UP | RIGHT => '╚',
+        c if c == UP | LEFT => '╝',
+        c if c & (LEFT | RIGHT) == 0 && c != 0 => '║',
+        // Lone walls and walls with only horizontal neighbours
+        _ => '═'
+    }
+}
+
+/// Returns the box drawing glyph for every tile in `tiles`. Tiles that aren't walls are `' '`
+pub fn box_glyphs(tiles: &Grid<Tile>) -> Grid<char> {
+    let (width, height) = tiles.dimensions();
+    let mut glyphs = Grid::new(width, height, ' ');
+
+    for (x, y, _) in tiles.iter().filter(|(_, _, &tile)| tile == Tile::Wall) {
+        glyphs[(x, y)] = box_glyph(wall_connections(tiles, conversions::as_i32(x), conversions::as_i32(y)));
+    }
+
+    glyphs
+}
+
+/// Recomputes the glyphs in `glyphs` around (x, y) after the tile there changed. Only the tile and its orthogonal
+/// neighbours can have different glyphs
+pub fn update_box_glyphs(glyphs: &mut Grid<char>, tiles: &Grid<Tile>, x: i32, y: i32) {
+    for (i, j) in [(x, y), (x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)].iter().copied() {
+        let glyph = match tiles.get(i, j) {
+            Some(Tile::Wall) => box_glyph(wall_connections(tiles, i, j)),
+            Some(_) => ' ',
+            None => continue
+        };
+        glyphs.set(i, j, glyph);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::map;
+
+    #[test]
+    fn test_box_glyph() {
+        assert_eq!(box_glyph(0), '═');
+        assert_eq!(box_glyph(LEFT | RIGHT), '═');
+        assert_eq!(box_glyph(UP), '║');
+        assert_eq!(box_glyph(DOWN | RIGHT), '╔');
+        assert_eq!(box_glyph(UP | DOWN | LEFT | RIGHT), '╬');
+    }
+
+    #[test]
+    fn test_room_outline() {
+        let map = map::test_room();
+        let glyph = |x, y| *map.wall_glyph(x, y).unwrap();
+
+        assert_eq!(glyph(2, 2), '╔');
+        assert_eq!(glyph(4, 2), '═');
+        assert_eq!(glyph(7, 2), '╗');
+        assert_eq!(glyph(7, 4), '║');
+        assert_eq!(glyph(7, 7), '╝');
+        assert_eq!(glyph(2, 7), '╚');
+        assert_eq!(glyph(4, 4), ' ');
+    }
+
+    #[test]
+    fn test_update_matches_full_recompute() {
+        let mut map = map::test_room();
+        map.set(4, 5, map::Tile::Wall);
+        map.set(4, 6, map::Tile::Wall);
+        map.set(4, 2, map::Tile::Blank);
+
+        let (width, height) = map.dimensions();
+        let mut tiles = Grid::new(width, height, Tile::Blank);
+        for (x, y, &tile) in map.rect(0, 0, width, height) {
+            tiles[(x, y)] = tile;
+        }
+        let expected = box_glyphs(&tiles);
+
+        for (x, y, glyph) in expected.iter() {
+            assert_eq!(map.wall_glyph(conversions::as_i32(x), conversions::as_i32(y)), Some(glyph), "({}, {})", x, y);
+        }
+    }
+}
@@ -1,77 +1,127 @@
-use std::ops::Index;
-use crate::utility::conversions;
+use crate::utility::grid::Grid;
+use super::autotile;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Tile {
     Blank,
     Wall
 }
 
-impl Clone for Tile {
-    fn clone(&self) -> Tile {
-        match self {
-            Self::Blank => Self::Blank,
-            Self::Wall => Self::Wall
-        }
-    }
-}
-
 /// Map of the game world
 #[derive(Default)]
 pub struct Map {
-    tiles: Vec<Vec<Tile>>
+    tiles: Grid<Tile>,
+    /// Tiles that can currently be seen
+    visible: Grid<bool>,
+    /// Tiles that have been seen at some point
+    revealed: Grid<bool>,
+    /// Box drawing glyph of each wall that connects it to the walls next to it. Kept up to date by `set` so it
+    /// isn't worked out again every frame
+    wall_glyphs: Grid<char>
 }
 
-impl Index<usize> for Map {
-
-    type Output = Vec<Tile>;
+impl Map {
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.tiles[index]
+    /// Returns `Map` of size `width` x `height` filled with `Tile::Blank`, with nothing seen yet
+    pub fn new(width: usize, height: usize) -> Self {
+        Map {
+            tiles: Grid::new(width, height, Tile::Blank),
+            visible: Grid::new(width, height, false),
+            revealed: Grid::new(width, height, false),
+            wall_glyphs: Grid::new(width, height, ' ')
+        }
     }
 
-}
-
-impl Map {
-
     /// Returns `(width, height)` of the map
     pub fn dimensions(&self) -> (usize, usize) {
-        if self.tiles.len() > 0 {
-            (self.tiles.len(), self.tiles[0].len())
-        } else {
-            (0, 0)
-        }
+        self.tiles.dimensions()
     }
 
     /// Returns `true` if (x, y) is in bounds of `self.tiles`
     pub fn in_bounds(&self, x: i32, y: i32) -> bool {
-        if self.tiles.len() > 0 {
-            let width = conversions::as_i32(self.tiles.len());
-            let height = conversions::as_i32(self.tiles[0].len());
+        self.tiles.in_bounds(x, y)
+    }
+
+    /// Returns the `Tile` at (x, y), or `None` if (x, y) is out of bounds
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.tiles.get(x, y)
+    }
 
-            (x >= 0 && x < width) && (y >= 0 && y < height)
-        } else {
-            false
+    /// Sets the `Tile` at (x, y). Does nothing if (x, y) is out of bounds
+    pub fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        if self.tiles.get(x, y).is_some_and(|&existing| existing != tile) {
+            self.tiles.set(x, y, tile);
+            autotile::update_box_glyphs(&mut self.wall_glyphs, &self.tiles, x, y);
         }
     }
 
+    /// Returns the box drawing glyph that connects the wall at (x, y) to the walls next to it, `' '` if (x, y) isn't
+    /// a wall, or `None` if (x, y) is out of bounds
+    pub fn wall_glyph(&self, x: i32, y: i32) -> Option<&char> {
+        self.wall_glyphs.get(x, y)
+    }
+
+    /// Iterates over each row of tiles from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[Tile]> {
+        self.tiles.rows()
+    }
+
+    /// Iterates over the tiles in the `width` x `height` rectangle with its top-left at (x, y), as `(x, y, tile)`
+    pub fn rect(&self, x: i32, y: i32, width: usize, height: usize) -> impl Iterator<Item = (usize, usize, &Tile)> {
+        self.tiles.rect(x, y, width, height)
+    }
+
+    /// Iterates over the in bounds orthogonal neighbours of (x, y), as `(x, y, tile)`
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32, &Tile)> {
+        self.tiles.neighbours(x, y)
+    }
+
+    /// Returns `true` if the tile at (x, y) can currently be seen
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        self.visible.get(x, y).copied().unwrap_or(false)
+    }
+
+    /// Returns `true` if the tile at (x, y) has been seen at some point
+    pub fn is_revealed(&self, x: i32, y: i32) -> bool {
+        self.revealed.get(x, y).copied().unwrap_or(false)
+    }
+
+    /// Marks the tile at (x, y) as visible, and so also revealed
+    pub fn reveal(&mut self, x: i32, y: i32) {
+        self.visible.set(x, y, true);
+        self.revealed.set(x, y, true);
+    }
+
+    /// Marks every tile as visible
+    pub fn reveal_all(&mut self) {
+        self.visible.fill(true);
+        self.revealed.fill(true);
+    }
+
+    /// Marks every tile as not visible. Tiles stay revealed
+    pub fn clear_visible(&mut self) {
+        self.visible.fill(false);
+    }
+
+    /// Returns `true` if the tile at (x, y) blocks line of sight. Spaces OOB don't block sight
+    pub fn blocks_sight(&self, x: i32, y: i32) -> bool {
+        matches!(self.tiles.get(x, y), Some(Tile::Wall))
+    }
+
     /// Returns `true` if (x, y) is able to be moved onto (has no collision).
     /// Spaces OOB are considered open
     ///
     /// Example: `Blank` returns `true` as you can move on it, but `Wall` returns `false`
     pub fn is_open(&self, x: i32, y: i32) -> bool {
-        if !self.in_bounds(x, y) {
-            return true
-        }
-
-        match self.tiles[conversions::as_usize(x)][conversions::as_usize(y)] {
-            Tile::Blank => true,
-            _ => false
+        match self.tiles.get(x, y) {
+            Some(tile) => *tile == Tile::Blank,
+            None => true
         }
     }
 }
 
 pub fn test_room() -> Map {
-    let mut tiles = vec![vec![Tile::Blank; 10]; 10];
+    let mut map = Map::new(10, 10);
 
     // Create 6x6 room in middle
     // \ 0 1 2 3 4 5 6 7 8 9
@@ -86,44 +136,44 @@ pub fn test_room() -> Map {
     // 8 . . . . . . . . . .
     // 9 . . . . . . . . . .
 
-    tiles[2][2] = Tile::Wall;
+    map.set(2, 2, Tile::Wall);
 
-    tiles[3][2] = Tile::Wall;
-    tiles[4][2] = Tile::Wall;
-    tiles[5][2] = Tile::Wall;
-    tiles[6][2] = Tile::Wall;
-    tiles[7][2] = Tile::Wall;
+    map.set(3, 2, Tile::Wall);
+    map.set(4, 2, Tile::Wall);
+    map.set(5, 2, Tile::Wall);
+    map.set(6, 2, Tile::Wall);
+    map.set(7, 2, Tile::Wall);
 
-    tiles[7][3] = Tile::Wall;
-    tiles[7][4] = Tile::Wall;
-    tiles[7][5] = Tile::Wall;
-    tiles[7][6] = Tile::Wall;
-    tiles[7][7] = Tile::Wall;
+    map.set(7, 3, Tile::Wall);
+    map.set(7, 4, Tile::Wall);
+    map.set(7, 5, Tile::Wall);
+    map.set(7, 6, Tile::Wall);
+    map.set(7, 7, Tile::Wall);
 
-    tiles[6][7] = Tile::Wall;
-    tiles[5][7] = Tile::Wall;
-    tiles[4][7] = Tile::Wall;
-    tiles[3][7] = Tile::Wall;
-    tiles[2][7] = Tile::Wall;
+    map.set(6, 7, Tile::Wall);
+    map.set(5, 7, Tile::Wall);
+    map.set(4, 7, Tile::Wall);
+    map.set(3, 7, Tile::Wall);
+    map.set(2, 7, Tile::Wall);
 
-    tiles[2][6] = Tile::Wall;
-    tiles[2][5] = Tile::Wall;
-    tiles[2][4] = Tile::Wall;
-    tiles[2][3] = Tile::Wall;
+    map.set(2, 6, Tile::Wall);
+    map.set(2, 5, Tile::Wall);
+    map.set(2, 4, Tile::Wall);
+    map.set(2, 3, Tile::Wall);
 
-    Map { tiles }
+    map
 }
 
 pub fn test_big_room() -> Map {
-    let mut tiles = vec![vec![Tile::Blank; 42]; 42];
+    let mut map = Map::new(42, 42);
 
     // Large blank room, with walls places to give feeling of motion
     for i in (0..42).step_by(5) {
         for j in (0..42).step_by(5) {
-            tiles[i][j] = Tile::Wall;
+            map.set(i, j, Tile::Wall);
 
         }
     }
 
-    Map { tiles }
+    map
 }
@@ -1 +1,3 @@
 pub mod map;
+pub mod autotile;
+pub mod path;
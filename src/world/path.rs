@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use crate::utility::conversions;
+use crate::utility::grid::Grid;
+use super::map::Map;
+
+/// Offsets of the orthogonal directions
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// Returns the shortest path from `from` to `to`, moving orthogonally over open tiles the player has seen. The path
+/// leaves out `from` and ends with `to`. Returns `None` if `to` can't be reached
+pub fn find_path(map: &Map, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    if !is_walkable(map, to.0, to.1) {
+        return None;
+    }
+
+    breadth_first(map, from, |x, y| (x, y) == to)
+}
+
+/// Returns the shortest path from `from` to the closest walkable tile next to one the player hasn't seen. Returns
+/// `None` once there is nowhere left to explore
+pub fn path_to_unexplored(map: &Map, from: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    breadth_first(map, from, |x, y| {
+        (x, y) != from && map.neighbours(x, y).any(|(next_x, next_y, _)| !map.is_revealed(next_x, next_y))
+    })
+}
+
+/// Returns the direction to keep running in after stepping onto `position` in the direction `heading`, or `None`
+/// if the run should stop there. Like classic roguelikes, runs follow corridors around bends and stop at dead ends
+/// and junctions, when going into or out of a room, when a wall is ahead, and beside an opening in the wall
+pub fn run_direction(map: &Map, position: (i32, i32), heading: (i32, i32)) -> Option<(i32, i32)> {
+    let offset = |(x, y): (i32, i32), (dx, dy): (i32, i32)| (x + dx, y + dy);
+    let previous = offset(position, (-heading.0, -heading.1));
+
+    let in_corridor = is_corridor(map, position);
+    let was_in_corridor = is_corridor(map, previous);
+
+    match (was_in_corridor, in_corridor) {
+        (true, true) => {
+            let mut exits = DIRECTIONS.iter()
+                .copied()
+                .filter(|&direction| offset(position, direction) != previous)
+                .filter(|&direction| {
+                    let (x, y) = offset(position, direction);
+                    is_walkable(map, x, y)
+                });
+
+            match (exits.next(), exits.next()) {
+                (Some(exit), None) => Some(exit),
+                _ => None
+            }
+        },
+        (false, false) => {
+            let sides = [(heading.1, heading.0), (-heading.1, -heading.0)];
+            let same_sides = sides.iter().all(|&side| {
+                let (x, y) = offset(position, side);
+                let (previous_x, previous_y) = offset(previous, side);
+                is_walkable(map, x, y) == is_walkable(map, previous_x, previous_y)
+            });
+
+            let (ahead_x, ahead_y) = offset(position, heading);
+            if same_sides && is_walkable(map, ahead_x, ahead_y) {
+                Some(heading)
+            } else {
+                None
+            }
+        },
+        // Going into or out of a room
+        _ => None
+    }
+}
+
+/// Returns `true` if (x, y) isn't part of any 2x2 square of walkable tiles, so it's part of a corridor rather than
+/// a room
+fn is_corridor(map: &Map, (x, y): (i32, i32)) -> bool {
+    let in_square = |left: i32, top: i32| {
+        [(0, 0), (1, 0), (0, 1), (1, 1)].iter().all(|&(dx, dy)| is_walkable(map, left + dx, top + dy))
+    };
+
+    ![(x - 1, y - 1), (x, y - 1), (x - 1, y), (x, y)].iter().any(|&(left, top)| in_square(left, top))
+}
+
+/// Searches outwards from `from` over walkable tiles, returning the path to the closest tile that `is_goal`
+fn breadth_first(map: &Map, from: (i32, i32), is_goal: impl Fn(i32, i32) -> bool) -> Option<Vec<(i32, i32)>> {
+    let (width, height) = map.dimensions();
+    // The tile each reached tile was first reached from
+    let mut came_from: Grid<Option<(i32, i32)>> = Grid::new(width, height, None);
+    let mut frontier = VecDeque::new();
+
+    came_from.set(from.0, from.1, Some(from));
+    frontier.push_back(from);
+
+    while let Some((x, y)) = frontier.pop_front() {
+        if is_goal(x, y) {
+            return Some(trace_back(&came_from, from, (x, y)));
+        }
+
+        for (next_x, next_y, _) in map.neighbours(x, y) {
+            let unvisited = came_from.get(next_x, next_y) == Some(&None);
+            if unvisited && is_walkable(map, next_x, next_y) {
+                came_from.set(next_x, next_y, Some((x, y)));
+                frontier.push_back((next_x, next_y));
+            }
+        }
+    }
+
+    None
+}
+
+/// Follows `came_from` back from `to` to `from`, returning the tiles in between in the order they are walked
+fn trace_back(came_from: &Grid<Option<(i32, i32)>>, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut path = vec!();
+    let mut current = to;
+
+    while current != from {
+        path.push(current);
+        current = came_from[(conversions::as_usize(current.0), conversions::as_usize(current.1))]
+            .expect("Every tile on the path was reached from another tile");
+    }
+
+    path.reverse();
+    path
+}
+
+/// Returns `true` if (x, y) is on the map, can be moved onto, and has been seen
+fn is_walkable(map: &Map, x: i32, y: i32) -> bool {
+    map.in_bounds(x, y) && map.is_open(x, y) && map.is_revealed(x, y)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::map::{self, Tile};
+
+    /// Returns a revealed map drawn by `rows`, where `#` is a wall and anything else is floor
+    fn draw_map(rows: &[&str]) -> Map {
+        let mut map = Map::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().enumerate() {
+                if character == '#' {
+                    map.set(x as i32, y as i32, Tile::Wall);
+                }
+            }
+        }
+        map.reveal_all();
+        map
+    }
+
+    /// Runs from `start` in the direction `heading`, returning where the run stops
+    fn run(map: &Map, start: (i32, i32), heading: (i32, i32)) -> (i32, i32) {
+        let mut position = (start.0 + heading.0, start.1 + heading.1);
+        let mut heading = heading;
+        while let Some(next) = run_direction(map, position, heading) {
+            position = (position.0 + next.0, position.1 + next.1);
+            heading = next;
+        }
+        position
+    }
+
+    #[test]
+    fn test_path_around_wall() {
+        let mut map = map::test_room();
+        map.reveal_all();
+
+        // From the left of the room to the right of it, going around the top or bottom
+        let path = find_path(&map, (1, 4), (8, 4)).unwrap();
+
+        assert_eq!(path.last(), Some(&(8, 4)));
+        assert!(!path.contains(&(1, 4)));
+        assert!(path.iter().all(|&(x, y)| map.is_open(x, y)));
+
+        let steps = std::iter::once((1, 4)).chain(path.iter().copied());
+        let adjacent = steps.clone().zip(path.iter())
+            .all(|((x, y), &(next_x, next_y))| (x - next_x).abs() + (y - next_y).abs() == 1);
+        assert!(adjacent);
+        assert_eq!(steps.count(), 14);
+    }
+
+    #[test]
+    fn test_no_path() {
+        let mut map = map::test_room();
+
+        // Nothing has been seen yet
+        assert_eq!(find_path(&map, (4, 4), (5, 5)), None);
+
+        map.reveal_all();
+        assert_eq!(find_path(&map, (4, 4), (2, 2)), None);
+        // The room is closed off
+        assert_eq!(find_path(&map, (4, 4), (9, 4)), None);
+        assert_eq!(find_path(&map, (4, 4), (20, 20)), None);
+        assert_eq!(find_path(&map, (4, 4), (4, 4)), Some(vec!()));
+    }
+
+    #[test]
+    fn test_path_to_unexplored() {
+        let mut map = map::test_room();
+        for x in 3..=5 {
+            map.reveal(x, 4);
+        }
+
+        // Either end of the revealed strip is next to tiles that haven't been seen
+        let path = path_to_unexplored(&map, (4, 4)).unwrap();
+        assert_eq!(path.len(), 1);
+        assert!(path[0] == (3, 4) || path[0] == (5, 4));
+
+        map.reveal_all();
+        assert_eq!(path_to_unexplored(&map, (4, 4)), None);
+    }
+
+    #[test]
+    fn test_run_follows_corridor() {
+        let map = draw_map(&[
+            "##########",
+            "#......###",
+            "#......###",
+            "#......###",
+            "#.........",
+            "######.###",
+            "######.#.#",
+            "######....",
+            "##########",
+        ]);
+
+        // Stops beside the way into the corridor below
+        assert_eq!(run(&map, (2, 4), (1, 0)), (6, 4));
+        // Leaving the room stops in the doorway
+        assert_eq!(run(&map, (6, 4), (1, 0)), (7, 4));
+        // Dead end
+        assert_eq!(run(&map, (7, 4), (1, 0)), (9, 4));
+        // Around the bend, then stops at the junction
+        assert_eq!(run(&map, (6, 5), (0, 1)), (8, 7));
+        // Entering the room stops
+        assert_eq!(run(&map, (6, 6), (0, -1)), (6, 4));
+    }
+
+    #[test]
+    fn test_run_in_room() {
+        let map = draw_map(&[
+            "####.####",
+            "#.......#",
+            "#.......#",
+            "#........",
+            "#.......#",
+            "#########",
+        ]);
+
+        // Stops at the wall ahead
+        assert_eq!(run(&map, (1, 1), (0, 1)), (1, 4));
+        // Stops beside the opening in the wall
+        assert_eq!(run(&map, (1, 1), (1, 0)), (4, 1));
+        assert_eq!(run(&map, (1, 3), (1, 0)), (8, 3));
+    }
+}